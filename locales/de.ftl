@@ -0,0 +1,425 @@
+# Avions deutscher Text. Siehe en-US.ftl für den Aufbau.
+
+language-name = Deutsch
+
+## Commands
+
+userinfo = nutzerinfo
+    .description = Zeigt deinen oder den Nutzernamen, Avatar und das Erstellungsdatum eines anderen Nutzers
+    .user = nutzer
+    .user-description = Ausgewählter Nutzer - standardmäßig du
+userinfo-menu = Nutzerinfo
+    .description = Zeigt den Nutzernamen, Avatar und das Erstellungsdatum eines Nutzers
+    .user = nutzer
+    .user-description = Der anzuzeigende Nutzer
+
+serverinfo = serverinfo
+    .description = Zeigt Eigentümer, Erstellungsdatum, Größe und Boost-Stufe dieses Servers an
+
+roleinfo = rolleninfo
+    .description = Zeigt Farbe, Position, Mitglieder, Berechtigungen und Erstellungsdatum einer Rolle an
+    .role = rolle
+    .role-description = Die anzuzeigende Rolle
+
+channelinfo = kanalinfo
+    .description = Zeigt Typ, Thema, Slowmode, Berechtigungsüberschreibungen und Erstellungsdatum eines Kanals an
+    .channel = kanal
+    .channel-description = Der anzuzeigende Kanal - standardmäßig dieser
+
+about = über
+    .description = Informationen über Avion
+
+avatar = avatar
+    .description = Zeigt den Avatar eines Nutzers
+avatar-user = nutzer
+    .description = Zeigt den globalen Avatar eines Nutzers.
+    .user = nutzer
+    .user-description = Ausgewählter Nutzer - standardmäßig du
+avatar-server = server
+    .description = Zeigt den Avatar eines Mitglieds auf diesem Server.
+    .user = nutzer
+    .user-description = Ausgewählter Nutzer - standardmäßig du
+avatar-menu = Avatar anzeigen
+    .description = Zeigt den Avatar eines Nutzers, wie er auf diesem Server aussieht
+    .user = nutzer
+    .user-description = Der Nutzer, dessen Avatar angezeigt wird
+avatar-message-menu = Avatar des Autors anzeigen
+    .description = Zeigt den Avatar des Autors einer Nachricht, wie er auf diesem Server aussieht
+    .message = nachricht
+    .message-description = Die Nachricht, deren Autor angezeigt wird
+
+banner = banner
+    .description = Zeigt das Profilbanner eines Nutzers.
+    .user = nutzer
+    .user-description = Ausgewählter Nutzer - standardmäßig du
+
+balance = kontostand
+    .description = Zeigt den Kontostand eines Nutzers auf dem Server.
+    .user = nutzer
+    .user-description = Ausgewählter Nutzer - standardmäßig du
+
+give = geben
+    .description = Gib einem anderen Nutzer Münzen.
+    .receiver = empfänger
+    .receiver-description = Ausgewählter Nutzer
+    .amount = betrag
+    .amount-description = Der Betrag, den du geben möchtest
+
+register = registrieren
+    .description = Registriere dein Konto in der Wirtschaft des Servers.
+
+privacy = datenschutz
+    .description = Sieh dir die Daten an, die Avion über dich speichert, oder lösche sie
+privacy-export = exportieren
+    .description = Schickt dir per DM eine Kopie von allem, was Avion über dich speichert.
+privacy-delete = löschen
+    .description = Löscht alles, was Avion über dich speichert, auf jedem Server.
+
+ecoadmin = ecoadmin
+    .description = Verwalte die Wirtschaft dieses Servers
+ecoadmin-export = exportieren
+    .description = Exportiere die Kontostände und Jobs dieses Servers.
+    .format = format
+    .format-description = Dateiformat - standardmäßig JSON
+ecoadmin-import = importieren
+    .description = Importiere Kontostände oder Jobs aus einem Avion-Export oder dem CSV- oder JSON-Export eines anderen Bots.
+    .file = datei
+    .file-description = Eine .json- oder .csv-Datei
+
+settings = einstellungen
+    .description = Ändere, wie Avion auf diesem Server funktioniert
+settings-commands = befehle
+    .description = Deaktiviere Befehle oder beschränke sie auf bestimmte Kanäle oder Rollen.
+    .command = befehl
+    .command-description = Der zu ändernde Befehl - leer lassen, um alle Änderungen zu sehen
+settings-language = sprache
+    .description = Lege fest, in welcher Sprache Avion antwortet, statt der Sprache jedes Mitglieds.
+    .language = sprache
+    .language-description = Die Sprache - leer lassen, um die Sprache jedes Mitglieds zu verwenden
+
+xkcd = xkcd
+    .description = Lies xkcd-Comics
+xkcd-today = heute
+    .description = Zeigt den heutigen Comic von XKCD.
+xkcd-comic = comic
+    .description = Zeigt einen bestimmten Comic von XKCD.
+    .num = nummer
+    .num-description = Comic-Nummer
+xkcd-random = zufall
+    .description = Zeigt einen zufälligen Comic von XKCD.
+xkcd-search = suchen
+    .description = Durchsuche XKCD-Comics nach Titel, Alt-Text und Transkript.
+    .query = suche
+    .query-description = Worum es in dem Comic geht
+xkcd-subscribe = abonnieren
+    .description = Poste neue XKCD-Comics in einem Kanal, sobald sie erscheinen.
+    .channel = kanal
+    .channel-description = Der Kanal, in dem gepostet wird - standardmäßig dieser
+xkcd-unsubscribe = deabonnieren
+    .description = Poste keine neuen XKCD-Comics mehr in einem Kanal.
+    .channel = kanal
+    .channel-description = Der Kanal, in dem nicht mehr gepostet wird - standardmäßig dieser
+
+feed = feed
+    .description = Lies Webcomics aus RSS- und Atom-Feeds
+feed-add = hinzufügen
+    .description = Füge diesem Server einen RSS- oder Atom-Feed eines Webcomics hinzu.
+    .name = name
+    .name-description = Wie der Feed heißen soll
+    .url = url
+    .url-description = Die Adresse des Feeds
+feed-remove = entfernen
+    .description = Entferne einen Feed samt seinen Abonnements von diesem Server.
+    .feed = feed
+    .feed-description = Der zu entfernende Feed
+feed-list = liste
+    .description = Zeigt die Feeds dieses Servers.
+feed-latest = neuester
+    .description = Zeigt den neuesten Comic eines Feeds.
+    .feed = feed
+    .feed-description = Der zu lesende Feed
+feed-random = zufall
+    .description = Zeigt einen zufälligen Comic eines Feeds.
+    .feed = feed
+    .feed-description = Der zu lesende Feed
+feed-comic = comic
+    .description = Zeigt einen bestimmten Comic eines Feeds, gezählt ab dem ersten, den Avion gesehen hat.
+    .feed = feed
+    .feed-description = Der zu lesende Feed
+    .num = nummer
+    .num-description = Nummer des Comics
+feed-subscribe = abonnieren
+    .description = Poste neue Comics eines Feeds in einem Kanal, sobald sie erscheinen.
+    .feed = feed
+    .feed-description = Der zu abonnierende Feed
+    .channel = kanal
+    .channel-description = Der Kanal, in dem gepostet wird - standardmäßig dieser
+feed-unsubscribe = deabonnieren
+    .description = Poste keine neuen Comics eines Feeds mehr in einem Kanal.
+    .feed = feed
+    .feed-description = Der nicht mehr zu abonnierende Feed
+    .channel = kanal
+    .channel-description = Der Kanal, in dem nicht mehr gepostet wird - standardmäßig dieser
+
+## Shared
+
+success-title = Erfolg!
+error-title = Fehler
+cancel = Abbrechen
+cancelled-title = Abgebrochen
+cancelled-command = Befehl abgebrochen.
+timed-out = Zeit abgelaufen
+yes = Ja
+no = Nein
+
+user-not-found-title = Nutzer nicht gefunden
+user-not-found-description = Dieser Nutzer hat Avion auf diesem Server vielleicht noch nicht benutzt.
+user-not-found-hint-title = Bist du das?
+user-not-found-hint = Dann führe einfach `/register` aus, um loszulegen.
+bots-not-allowed-title = Bots sind bei diesem Befehl nicht erlaubt
+bots-not-allowed-description = Du kannst diesen Befehl nicht mit Bots verwenden.
+cannot-use-yourself-title = Moment mal...
+cannot-use-yourself-description = Du kannst diesen Befehl nicht mit dir selbst verwenden!
+bot-restarting-title = Avion startet neu
+bot-restarting-description = Bitte versuche es gleich noch einmal.
+on-cooldown-title = Langsam!
+on-cooldown-description = Du kannst diesen Befehl { $when } wieder verwenden.
+
+command-unavailable-title = Befehl nicht verfügbar
+command-disabled = Dieser Befehl wurde auf diesem Server deaktiviert.
+command-wrong-channel = Dieser Befehl kann nur in { $channels } verwendet werden.
+command-missing-role = Dieser Befehl kann nur von Mitgliedern mit { $roles } verwendet werden.
+
+## Replies
+
+userinfo-display-name = Anzeigename
+userinfo-created = Konto erstellt am
+userinfo-id = Nutzer-ID
+userinfo-bot = Ist ein Bot
+userinfo-nickname = Spitzname
+userinfo-joined = Server beigetreten
+userinfo-boosting = Boostet
+userinfo-boosting-since = Seit { $since }
+userinfo-roles = Rollen ({ $count })
+userinfo-more-roles = und { $count } weitere
+userinfo-permissions = Wichtige Berechtigungen
+userinfo-balance = Avion-Guthaben
+userinfo-balance-value = { $wallet } in der Geldbörse, { $bank } auf der Bank
+userinfo-none = Keine
+userinfo-unknown = Unbekannt
+userinfo-permission-administrator = Administrator
+userinfo-permission-manage-server = Server verwalten
+userinfo-permission-manage-roles = Rollen verwalten
+userinfo-permission-manage-channels = Kanäle verwalten
+userinfo-permission-manage-messages = Nachrichten verwalten
+userinfo-permission-manage-nicknames = Spitznamen verwalten
+userinfo-permission-manage-webhooks = Webhooks verwalten
+userinfo-permission-kick-members = Mitglieder kicken
+userinfo-permission-ban-members = Mitglieder bannen
+userinfo-permission-timeout-members = Mitglieder timeouten
+userinfo-permission-mention-everyone = @everyone erwähnen
+
+serverinfo-owner = Eigentümer
+serverinfo-created = Erstellungsdatum
+serverinfo-members = Mitglieder
+serverinfo-channels = Kanäle
+serverinfo-roles = Rollen
+serverinfo-boosts = Boosts
+serverinfo-boost-level = { $level ->
+        [0] Keine Stufe
+       *[other] Stufe { $level }
+    } ({ $boosts ->
+        [one] 1 Boost
+       *[other] { $boosts } Boosts
+    })
+serverinfo-verification = Verifizierungsstufe
+serverinfo-verification-level = { $level ->
+        [0] Keine
+        [1] Niedrig
+        [2] Mittel
+        [3] Hoch
+        [4] Am höchsten
+       *[other] Unbekannt
+    }
+serverinfo-features = Funktionen
+serverinfo-no-features = Keine
+serverinfo-id = Server-ID: { $id }
+serverinfo-unavailable = Avion kann nur Infos zu Servern anzeigen, zu denen es hinzugefügt wurde.
+
+roleinfo-colour = Farbe
+roleinfo-position = Position
+roleinfo-members = Mitglieder
+roleinfo-created = Erstellungsdatum
+roleinfo-mentionable = Erwähnbar
+roleinfo-hoisted = Separat angezeigt
+roleinfo-id = Rollen-ID: { $id }
+
+channelinfo-type = Typ
+channelinfo-type-name = { $kind ->
+        [0] Text
+        [2] Sprache
+        [4] Kategorie
+        [5] Ankündigungen
+        [10] Ankündigungs-Thread
+        [11] Thread
+        [12] Privater Thread
+        [13] Stage
+        [14] Verzeichnis
+        [15] Forum
+        [16] Medien
+       *[other] Unbekannt
+    }
+channelinfo-created = Erstellungsdatum
+channelinfo-slowmode = Slowmode
+channelinfo-slowmode-off = Aus
+channelinfo-category = Kategorie
+channelinfo-topic = Thema
+channelinfo-overwrites = Berechtigungsüberschreibungen ({ $count })
+channelinfo-overwrite = { $target }: { $allowed } erlaubt, { $denied } verweigert
+channelinfo-more-overwrites = und { $count } weitere
+channelinfo-nsfw = Dieser Kanal ist altersbeschränkt.
+channelinfo-id = Kanal-ID: { $id }
+
+about-title = Über Avion
+about-version = Version
+about-build-timestamp = Build-Zeitpunkt
+about-rust-version = Rust-Version
+about-git-commit = Git-Commit
+about-pool = Datenbank-Pool
+about-pool-value = { $active } aktiv, { $idle } frei ({ $max } max.)
+about-unavailable = Nicht verfügbar
+about-connection-wait = Wartezeit auf Verbindung
+about-shard = Shard
+about-shard-value = #{ $id } von { $total }
+about-gateway-latency = Gateway-Latenz
+about-footer = Lizenzen von Drittanbietern: TODO (frag @justhypex)
+
+avatar-title = Avatar von @{ $username }
+avatar-server-title = Server-Avatar von @{ $username }
+avatar-no-server-avatar = @{ $username } hat keinen eigenen Avatar für diesen Server, daher ist das der globale.
+avatar-not-member = @{ $username } ist nicht auf diesem Server.
+
+banner-title = Banner von @{ $username }
+banner-none = @{ $username } hat kein Banner.
+
+balance-title = Kontostände von @{ $username }
+balance-wallet = Geldbörse
+balance-bank = Bank
+
+register-success = Dein Konto wurde in der Wirtschaft des Servers registriert!
+register-already-registered = Du bist bereits in der Wirtschaft des Servers registriert!
+
+give-confirm = Geben
+give-confirm-title = An @{ $username } geben?
+give-confirm-description =
+    Möchtest du **@{ $username }** wirklich **{ $amount }** Münzen geben?
+
+    Deine künftigen Kontostände stehen unten.
+give-your-wallet = Deine Geldbörse
+give-their-wallet = Geldbörse von @{ $username }
+give-success = Die neuen Kontostände stehen unten.
+give-not-enough-title = Nicht genug Geld
+give-not-enough-description = Dir fehlen **{ $shortfall }** Münzen, um **{ $amount }** zu geben.
+
+privacy-export-message = Hier ist alles, was Avion über dich speichert.
+privacy-export-sent-title = Sieh in deine DMs
+privacy-export-sent-description = Ich habe dir eine Kopie deiner Daten geschickt.
+privacy-export-failed-title = Ich konnte dir keine DM schicken
+privacy-export-failed-description = Erlaube Direktnachrichten von Mitgliedern dieses Servers und versuche es dann erneut.
+privacy-delete-confirm = Löschen
+privacy-delete-confirm-title = Deine Daten löschen?
+privacy-delete-confirm-description = Das löscht deine Kontostände auf **jedem** Server, auf dem Avion ist. Das kann nicht rückgängig gemacht werden.
+privacy-delete-nothing-deleted = Es wurde nichts gelöscht.
+privacy-deleted-title = Deine Daten wurden gelöscht
+privacy-deleted-description = { $rows } Einträge gelöscht.
+privacy-deleted-held-title = Einige Daten wurden behalten
+privacy-deleted-held-description = Diese Server unterliegen einer rechtlichen Aufbewahrungspflicht, daher müssen deine Daten dort behalten werden: { $guilds }
+
+ecoadmin-export-title = Wirtschaftsexport
+ecoadmin-export-description = **{ $accounts }** Konten und **{ $jobs }** Jobs exportiert.
+ecoadmin-import-unreadable = Die Datei konnte nicht gelesen werden
+ecoadmin-import-too-large = Die Datei ist zu groß.
+ecoadmin-import-wrong-type = Nur `.json`- und `.csv`-Dateien können importiert werden.
+ecoadmin-import-nothing-title = Nichts zu importieren
+ecoadmin-import-nothing-description = Dieser Server entspricht bereits der Datei.
+ecoadmin-import-confirm = Importieren
+ecoadmin-import-success = **{ $accounts }** Konten und **{ $jobs }** Jobs importiert.
+ecoadmin-import-nothing-imported = Es wurde nichts importiert.
+ecoadmin-preview-title = Diese Änderungen importieren?
+ecoadmin-preview-changed = { $user }: Geldbörse { $old_wallet } → { $wallet }, Bank { $old_bank } → { $bank }
+ecoadmin-preview-added = { $user }: neu, Geldbörse { $wallet }, Bank { $bank }
+ecoadmin-preview-more = …und { $remaining } weitere
+ecoadmin-preview-new-accounts = Neue Konten
+ecoadmin-preview-changed-accounts = Geänderte Konten
+ecoadmin-preview-reassigned-accounts = Konten mit neuem Job
+ecoadmin-preview-unchanged-accounts = Unveränderte Konten
+ecoadmin-preview-jobs = Jobs
+ecoadmin-preview-jobs-value = { $added } neu, { $changed } geändert
+ecoadmin-preview-footer = Konten und Jobs, die in der Datei fehlen, bleiben unverändert.
+
+settings-unknown-command-title = Unbekannter Befehl
+settings-unknown-command-description = Es gibt keinen Befehl `/{ $command }`, der geändert werden kann.
+settings-any = Alle
+settings-enabled = Aktiviert
+settings-disabled = Deaktiviert
+settings-panel-title = Einstellungen für /{ $command }
+settings-panel-description = Mitglieder, die den Server verwalten können, können immer jeden Befehl verwenden.
+settings-status = Status
+settings-channels = Kanäle
+settings-roles = Rollen
+settings-enable = Aktivieren
+settings-disable = Deaktivieren
+settings-reset = Zurücksetzen
+settings-channels-placeholder = Erlaubte Kanäle (alle, wenn leer)
+settings-roles-placeholder = Erlaubte Rollen (alle, wenn leer)
+settings-overview-title = Befehlseinstellungen
+settings-overview-empty = Jeder Befehl ist überall aktiviert. Wähle einen Befehl, um ihn zu ändern.
+settings-overview-command =
+    { $status }
+    Kanäle: { $channels }
+    Rollen: { $roles }
+settings-language-unknown-title = Unbekannte Sprache
+settings-language-unknown-description = Wähle eine Sprache aus der Liste.
+settings-language-set = Avion antwortet auf diesem Server auf { $language }.
+settings-language-cleared = Avion antwortet in der Sprache jedes Mitglieds.
+
+comic-not-found-title = Comic nicht gefunden
+comic-not-found-description = Den angeforderten Comic scheint es nicht zu geben. Tut uns leid!
+xkcd-not-found-404 = Es gibt keinen Comic 404. Das ist der Witz.
+comic-unavailable-title = { $source } ist nicht erreichbar
+comic-timeout-description = { $source } antwortet gerade zu langsam. Versuche es gleich noch einmal.
+comic-invalid-feed-description = { $source } ist kein RSS- oder Atom-Feed, den Avion lesen kann.
+comic-private-address-description = { $source } liegt in einem privaten Netzwerk, mit dem Avion sich nicht verbindet.
+comic-upstream-description = Avion konnte den Comic nicht von { $source } laden. Versuche es gleich noch einmal.
+comic-feed-removed-title = Feed entfernt
+comic-feed-removed-description = Dieser Feed wurde vom Server entfernt, daher kann nicht mehr durch seine Comics geblättert werden.
+xkcd-search-title = Comics zu „{ $query }“
+xkcd-search-placeholder = Wähle einen Comic
+xkcd-search-empty-title = Keine Comics gefunden
+xkcd-search-empty-description = Versuche es mit anderen Wörtern, oder schau später noch einmal, falls der Comic neu ist.
+xkcd-subscribed = Neue Comics werden in { $channel } gepostet.
+xkcd-already-subscribed = Neue Comics werden bereits in { $channel } gepostet.
+xkcd-unsubscribed = Neue Comics werden nicht mehr in { $channel } gepostet.
+xkcd-not-subscribed = In { $channel } werden keine neuen Comics gepostet.
+xkcd-published = Veröffentlicht
+xkcd-link = Link
+xkcd-transcript = Transkript
+comic-button-previous = Zurück
+comic-button-random = Zufällig
+comic-button-next = Weiter
+xkcd-button-explain = Erklärung
+
+feed-added = Der Feed **{ $name }** wurde hinzugefügt.
+feed-name-taken = Es gibt bereits einen Feed namens **{ $name }**.
+feed-invalid-name = Feed-Namen dürfen nicht leer oder länger als { $max } Zeichen sein.
+feed-invalid-url = Das ist keine Webadresse. Sie sollte mit `https://` beginnen.
+feed-too-many = Dieser Server hat bereits { $max } Feeds. Entferne einen, um einen weiteren hinzuzufügen.
+feed-removed = Der Feed **{ $name }** wurde entfernt.
+feed-not-found-title = Feed nicht gefunden
+feed-not-found-description = Dieser Server hat keinen Feed namens **{ $name }**.
+feed-list-title = Feeds
+feed-list-empty = Dieser Server hat noch keine Feeds. Füge einen mit `/feed hinzufügen` hinzu.
+feed-subscribed = Neue Comics von { $name } werden in { $channel } gepostet.
+feed-already-subscribed = Neue Comics von { $name } werden bereits in { $channel } gepostet.
+feed-unsubscribed = Neue Comics von { $name } werden nicht mehr in { $channel } gepostet.
+feed-not-subscribed = In { $channel } werden keine neuen Comics von { $name } gepostet.
@@ -0,0 +1,428 @@
+# Avion's English text, which every other locale falls back to.
+#
+# Commands use their name as the message ID (subcommands joined with dashes), with
+# attributes for the description, and for each parameter's name and description.
+
+language-name = English
+
+## Commands
+
+userinfo = userinfo
+    .description = Displays your or another user's username, avatar, and account creation date
+    .user = user
+    .user-description = Selected user - defaults to you
+userinfo-menu = User Info
+    .description = Displays a user's username, avatar, and account creation date
+    .user = user
+    .user-description = The user to show
+
+serverinfo = serverinfo
+    .description = Displays this server's owner, creation date, size, and boost level
+
+roleinfo = roleinfo
+    .description = Displays a role's colour, position, members, permissions, and creation date
+    .role = role
+    .role-description = The role to show
+
+channelinfo = channelinfo
+    .description = Displays a channel's type, topic, slowmode, permission overwrites, and creation date
+    .channel = channel
+    .channel-description = The channel to show - defaults to this one
+
+about = about
+    .description = Information about Avion
+
+avatar = avatar
+    .description = Get a user's avatar
+avatar-user = user
+    .description = Gets a user's global avatar.
+    .user = user
+    .user-description = Selected user - defaults to you
+avatar-server = server
+    .description = Gets a member's avatar in this server.
+    .user = user
+    .user-description = Selected user - defaults to you
+avatar-menu = View Avatar
+    .description = Gets a user's avatar, as shown in this server
+    .user = user
+    .user-description = The user whose avatar to show
+avatar-message-menu = View Author's Avatar
+    .description = Gets a message author's avatar, as shown in this server
+    .message = message
+    .message-description = The message whose author's avatar to show
+
+banner = banner
+    .description = Gets a user's profile banner.
+    .user = user
+    .user-description = Selected user - defaults to you
+
+balance = balance
+    .description = Gets a user's balance in the server.
+    .user = user
+    .user-description = Selected user - defaults to you
+
+give = give
+    .description = Give coins to another user.
+    .receiver = receiver
+    .receiver-description = Selected user
+    .amount = amount
+    .amount-description = The amount to give
+
+register = register
+    .description = Register your user account in the server economy.
+
+privacy = privacy
+    .description = See or delete the data Avion stores about you
+privacy-export = export
+    .description = DMs you a copy of everything Avion stores about you.
+privacy-delete = delete
+    .description = Deletes everything Avion stores about you, in every server.
+
+ecoadmin = ecoadmin
+    .description = Manage this server's economy
+ecoadmin-export = export
+    .description = Export this server's balances and jobs.
+    .format = format
+    .format-description = File format - defaults to JSON
+ecoadmin-import = import
+    .description = Import balances or jobs from an Avion export, or another bot's CSV or JSON export.
+    .file = file
+    .file-description = A .json or .csv file
+
+settings = settings
+    .description = Change how Avion works in this server
+settings-commands = commands
+    .description = Disable commands, or restrict them to some channels or roles.
+    .command = command
+    .command-description = The command to change - leave empty to see every change
+settings-language = language
+    .description = Set the language Avion replies in, instead of each member's own.
+    .language = language
+    .language-description = The language to use - leave empty to use each member's own
+
+xkcd = xkcd
+    .description = Read xkcd comics
+xkcd-today = today
+    .description = Get today's comic from XKCD.
+xkcd-comic = comic
+    .description = Get a specific comic from XKCD.
+    .num = num
+    .num-description = Comic number
+xkcd-random = random
+    .description = Get a random comic from XKCD.
+xkcd-search = search
+    .description = Search XKCD comics by title, alt text and transcript.
+    .query = query
+    .query-description = What the comic is about
+xkcd-subscribe = subscribe
+    .description = Post new XKCD comics in a channel as they come out.
+    .channel = channel
+    .channel-description = The channel to post in - defaults to this one
+xkcd-unsubscribe = unsubscribe
+    .description = Stop posting new XKCD comics in a channel.
+    .channel = channel
+    .channel-description = The channel to stop posting in - defaults to this one
+
+feed = feed
+    .description = Read web comics from RSS and Atom feeds
+feed-add = add
+    .description = Add an RSS or Atom feed of a web comic to this server.
+    .name = name
+    .name-description = What to call the feed
+    .url = url
+    .url-description = The feed's address
+feed-remove = remove
+    .description = Remove a feed from this server, along with its subscriptions.
+    .feed = feed
+    .feed-description = The feed to remove
+feed-list = list
+    .description = List this server's feeds.
+feed-latest = latest
+    .description = Get the latest comic from a feed.
+    .feed = feed
+    .feed-description = The feed to read
+feed-random = random
+    .description = Get a random comic from a feed.
+    .feed = feed
+    .feed-description = The feed to read
+feed-comic = comic
+    .description = Get a specific comic from a feed, numbered from the first one Avion saw.
+    .feed = feed
+    .feed-description = The feed to read
+    .num = num
+    .num-description = Comic number
+feed-subscribe = subscribe
+    .description = Post a feed's new comics in a channel as they come out.
+    .feed = feed
+    .feed-description = The feed to follow
+    .channel = channel
+    .channel-description = The channel to post in - defaults to this one
+feed-unsubscribe = unsubscribe
+    .description = Stop posting a feed's new comics in a channel.
+    .feed = feed
+    .feed-description = The feed to stop following
+    .channel = channel
+    .channel-description = The channel to stop posting in - defaults to this one
+
+## Shared
+
+success-title = Success!
+error-title = Error
+cancel = Cancel
+cancelled-title = Cancelled
+cancelled-command = Cancelled command.
+timed-out = Timed out
+yes = Yes
+no = No
+
+user-not-found-title = User not found
+user-not-found-description = This user may not have used Avion in this server before.
+user-not-found-hint-title = Is this you?
+user-not-found-hint = If so, just run `/register` to get started.
+bots-not-allowed-title = Bots not allowed with this command
+bots-not-allowed-description = You can't use this command with bots.
+cannot-use-yourself-title = Wait a second...
+cannot-use-yourself-description = You can't use this command with yourself!
+bot-restarting-title = Avion is restarting
+bot-restarting-description = Please try again in a moment.
+on-cooldown-title = Slow down!
+on-cooldown-description = You can use this command again { $when }.
+
+command-unavailable-title = Command unavailable
+command-disabled = This command has been disabled in this server.
+command-wrong-channel = This command can only be used in { $channels }.
+command-missing-role = This command can only be used by members with { $roles }.
+
+## Replies
+
+userinfo-display-name = Display name
+userinfo-created = Account creation date
+userinfo-id = User ID
+userinfo-bot = Is bot
+userinfo-nickname = Nickname
+userinfo-joined = Joined server
+userinfo-boosting = Boosting
+userinfo-boosting-since = Since { $since }
+userinfo-roles = Roles ({ $count })
+userinfo-more-roles = and { $count } more
+userinfo-permissions = Key permissions
+userinfo-balance = Avion balance
+userinfo-balance-value = { $wallet } in wallet, { $bank } in bank
+userinfo-none = None
+userinfo-unknown = Unknown
+userinfo-permission-administrator = Administrator
+userinfo-permission-manage-server = Manage Server
+userinfo-permission-manage-roles = Manage Roles
+userinfo-permission-manage-channels = Manage Channels
+userinfo-permission-manage-messages = Manage Messages
+userinfo-permission-manage-nicknames = Manage Nicknames
+userinfo-permission-manage-webhooks = Manage Webhooks
+userinfo-permission-kick-members = Kick Members
+userinfo-permission-ban-members = Ban Members
+userinfo-permission-timeout-members = Timeout Members
+userinfo-permission-mention-everyone = Mention Everyone
+
+serverinfo-owner = Owner
+serverinfo-created = Creation date
+serverinfo-members = Members
+serverinfo-channels = Channels
+serverinfo-roles = Roles
+serverinfo-boosts = Boosts
+serverinfo-boost-level = { $level ->
+        [0] No level
+       *[other] Level { $level }
+    } ({ $boosts ->
+        [one] 1 boost
+       *[other] { $boosts } boosts
+    })
+serverinfo-verification = Verification level
+serverinfo-verification-level = { $level ->
+        [0] None
+        [1] Low
+        [2] Medium
+        [3] High
+        [4] Highest
+       *[other] Unknown
+    }
+serverinfo-features = Features
+serverinfo-no-features = None
+serverinfo-id = Server ID: { $id }
+serverinfo-unavailable = Avion can only show info about servers it's been added to.
+
+roleinfo-colour = Colour
+roleinfo-position = Position
+roleinfo-members = Members
+roleinfo-created = Creation date
+roleinfo-mentionable = Mentionable
+roleinfo-hoisted = Shown separately
+roleinfo-id = Role ID: { $id }
+
+channelinfo-type = Type
+channelinfo-type-name = { $kind ->
+        [0] Text
+        [2] Voice
+        [4] Category
+        [5] Announcement
+        [10] Announcement thread
+        [11] Thread
+        [12] Private thread
+        [13] Stage
+        [14] Directory
+        [15] Forum
+        [16] Media
+       *[other] Unknown
+    }
+channelinfo-created = Creation date
+channelinfo-slowmode = Slowmode
+channelinfo-slowmode-off = Off
+channelinfo-category = Category
+channelinfo-topic = Topic
+channelinfo-overwrites = Permission overwrites ({ $count })
+channelinfo-overwrite = { $target }: { $allowed } allowed, { $denied } denied
+channelinfo-more-overwrites = and { $count } more
+channelinfo-nsfw = This channel is age-restricted.
+channelinfo-id = Channel ID: { $id }
+
+about-title = About Avion
+about-version = Version
+about-build-timestamp = Build timestamp
+about-rust-version = Rust version
+about-git-commit = Git commit
+about-pool = Database pool
+about-pool-value = { $active } active, { $idle } idle ({ $max } max)
+about-unavailable = Unavailable
+about-connection-wait = Connection wait
+about-shard = Shard
+about-shard-value = #{ $id } of { $total }
+about-gateway-latency = Gateway latency
+about-footer = Third-party licenses: TODO (ask @justhypex)
+
+avatar-title = @{ $username }'s avatar
+avatar-server-title = @{ $username }'s server avatar
+avatar-no-server-avatar = @{ $username } has no avatar for this server, so this is their global one.
+avatar-not-member = @{ $username } isn't in this server.
+
+banner-title = @{ $username }'s banner
+banner-none = @{ $username } has no banner.
+
+balance-title = @{ $username }'s balances
+balance-wallet = Wallet Balance
+balance-bank = Bank Balance
+
+register-success = Successfully registered your account in the server economy!
+register-already-registered = You are already registered in the server economy!
+
+give-confirm = Give
+give-confirm-title = Give to @{ $username }?
+give-confirm-description =
+    Are you sure you want to give **{ $amount }** coins to **@{ $username }**?
+
+    Your future balances are below.
+give-your-wallet = Your wallet balance
+give-their-wallet = @{ $username }'s wallet balance
+give-success = The new balances are below.
+give-not-enough-title = Not enough money
+give-not-enough-description = You need **{ $shortfall }** more coins to give **{ $amount }**.
+
+privacy-export-message = Here's everything Avion stores about you.
+privacy-export-sent-title = Check your DMs
+privacy-export-sent-description = I've sent you a copy of your data.
+privacy-export-failed-title = Couldn't DM you
+privacy-export-failed-description = Allow direct messages from this server's members, then try again.
+privacy-delete-confirm = Delete
+privacy-delete-confirm-title = Delete your data?
+privacy-delete-confirm-description = This deletes your balances in **every** server Avion is in. It can't be undone.
+privacy-delete-nothing-deleted = Nothing was deleted.
+privacy-deleted-title = Your data was deleted
+privacy-deleted-description = Deleted { $rows } records.
+privacy-deleted-held-title = Some data was kept
+privacy-deleted-held-description = These servers are under a legal hold, so your data there must be kept: { $guilds }
+
+ecoadmin-export-title = Economy export
+ecoadmin-export-description = Exported **{ $accounts }** accounts and **{ $jobs }** jobs.
+ecoadmin-import-unreadable = Couldn't read the file
+ecoadmin-import-too-large = The file is too large.
+ecoadmin-import-wrong-type = Only `.json` and `.csv` files can be imported.
+ecoadmin-import-nothing-title = Nothing to import
+ecoadmin-import-nothing-description = This server already matches the file.
+ecoadmin-import-confirm = Import
+ecoadmin-import-success = Imported **{ $accounts }** accounts and **{ $jobs }** jobs.
+ecoadmin-import-nothing-imported = Nothing was imported.
+ecoadmin-preview-title = Import these changes?
+ecoadmin-preview-changed = { $user }: wallet { $old_wallet } → { $wallet }, bank { $old_bank } → { $bank }
+ecoadmin-preview-added = { $user }: new, wallet { $wallet }, bank { $bank }
+ecoadmin-preview-more = …and { $remaining } more
+ecoadmin-preview-new-accounts = New accounts
+ecoadmin-preview-changed-accounts = Changed accounts
+ecoadmin-preview-reassigned-accounts = Accounts with a new job
+ecoadmin-preview-unchanged-accounts = Unchanged accounts
+ecoadmin-preview-jobs = Jobs
+ecoadmin-preview-jobs-value = { $added } new, { $changed } changed
+ecoadmin-preview-footer = Accounts and jobs missing from the file are left as they are.
+
+settings-unknown-command-title = Unknown command
+settings-unknown-command-description = There's no `/{ $command }` command to change.
+settings-any = Any
+settings-enabled = Enabled
+settings-disabled = Disabled
+settings-panel-title = /{ $command } settings
+settings-panel-description = Members who can manage the server can always use every command.
+settings-status = Status
+settings-channels = Channels
+settings-roles = Roles
+settings-enable = Enable
+settings-disable = Disable
+settings-reset = Reset
+settings-channels-placeholder = Allowed channels (any if empty)
+settings-roles-placeholder = Allowed roles (anyone if empty)
+settings-overview-title = Command settings
+settings-overview-empty = Every command is enabled everywhere. Pick a command to change it.
+settings-overview-command =
+    { $status }
+    Channels: { $channels }
+    Roles: { $roles }
+settings-language-unknown-title = Unknown language
+settings-language-unknown-description = Pick a language from the list.
+settings-language-set = Avion will reply in { $language } in this server.
+settings-language-cleared = Avion will reply in each member's own language.
+
+comic-not-found-title = Comic not found
+comic-not-found-description = It looks like the comic you requested does not exist. Sorry!
+xkcd-not-found-404 = There's no comic 404. That's the joke.
+comic-unavailable-title = { $source } is unavailable
+comic-timeout-description = { $source } is taking too long to respond. Try again in a bit.
+comic-invalid-feed-description = { $source } isn't an RSS or Atom feed Avion can read.
+comic-private-address-description = { $source } is on a private network, which Avion won't connect to.
+comic-upstream-description = Avion couldn't get the comic from { $source }. Try again in a bit.
+comic-feed-removed-title = Feed removed
+comic-feed-removed-description = This feed has been removed from the server, so its comics can't be paged through anymore.
+xkcd-search-title = Comics about "{ $query }"
+xkcd-search-placeholder = Pick a comic
+xkcd-search-empty-title = No comics found
+xkcd-search-empty-description = Try different words, or check back later if the comic is new.
+xkcd-subscribed = New comics will be posted in { $channel }.
+xkcd-already-subscribed = New comics are already posted in { $channel }.
+xkcd-unsubscribed = New comics won't be posted in { $channel } anymore.
+xkcd-not-subscribed = New comics aren't posted in { $channel }.
+xkcd-published = Published
+xkcd-link = Link
+xkcd-transcript = Transcript
+comic-button-previous = Previous
+comic-button-random = Random
+comic-button-next = Next
+xkcd-button-explain = Explain
+
+feed-added = Added the feed **{ $name }**.
+feed-name-taken = There's already a feed called **{ $name }**.
+feed-invalid-name = Feed names can't be empty or longer than { $max } characters.
+feed-invalid-url = That isn't a web address. It should start with `https://`.
+feed-too-many = This server already has { $max } feeds. Remove one to add another.
+feed-removed = Removed the feed **{ $name }**.
+feed-not-found-title = Feed not found
+feed-not-found-description = This server has no feed called **{ $name }**.
+feed-list-title = Feeds
+feed-list-empty = This server has no feeds yet. Add one with `/feed add`.
+feed-subscribed = New comics from { $name } will be posted in { $channel }.
+feed-already-subscribed = New comics from { $name } are already posted in { $channel }.
+feed-unsubscribed = New comics from { $name } won't be posted in { $channel } anymore.
+feed-not-subscribed = New comics from { $name } aren't posted in { $channel }.
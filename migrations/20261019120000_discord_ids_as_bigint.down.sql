@@ -0,0 +1,14 @@
+-- Down migration
+ALTER TABLE users
+ALTER COLUMN user_id TYPE text USING user_id::text,
+ALTER COLUMN guild_id TYPE text USING guild_id::text,
+ALTER COLUMN guild_id SET DEFAULT '';
+
+ALTER TABLE jobs
+ALTER COLUMN guild_id TYPE text USING guild_id::text;
+
+INSERT INTO users SELECT * FROM users_without_guild;
+INSERT INTO jobs SELECT * FROM jobs_without_guild;
+
+DROP TABLE users_without_guild;
+DROP TABLE jobs_without_guild;
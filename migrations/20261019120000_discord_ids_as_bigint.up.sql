@@ -0,0 +1,34 @@
+-- Up migration
+-- Rows from before balances were guild specific have an empty guild_id and can't be
+-- attributed to any guild, so move them (and any other IDs that aren't snowflakes) aside
+-- instead of failing the cast.
+CREATE TABLE users_without_guild AS
+SELECT * FROM users
+WHERE guild_id !~ '^[0-9]+$' OR user_id !~ '^[0-9]+$';
+
+DELETE FROM users
+WHERE guild_id !~ '^[0-9]+$' OR user_id !~ '^[0-9]+$';
+
+CREATE TABLE jobs_without_guild AS
+SELECT * FROM jobs
+WHERE guild_id !~ '^[0-9]+$';
+
+DELETE FROM jobs
+WHERE guild_id !~ '^[0-9]+$';
+
+DO $$
+DECLARE
+    users_moved bigint := (SELECT count(*) FROM users_without_guild);
+    jobs_moved bigint := (SELECT count(*) FROM jobs_without_guild);
+BEGIN
+    RAISE NOTICE 'Moved % users to users_without_guild and % jobs to jobs_without_guild',
+        users_moved, jobs_moved;
+END $$;
+
+ALTER TABLE users
+ALTER COLUMN guild_id DROP DEFAULT,
+ALTER COLUMN user_id TYPE bigint USING user_id::bigint,
+ALTER COLUMN guild_id TYPE bigint USING guild_id::bigint;
+
+ALTER TABLE jobs
+ALTER COLUMN guild_id TYPE bigint USING guild_id::bigint;
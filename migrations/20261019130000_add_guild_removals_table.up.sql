@@ -0,0 +1,6 @@
+-- Up migration
+-- Guilds Avion has been removed from, whose data is purged once the grace period is over.
+CREATE TABLE guild_removals (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    removed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
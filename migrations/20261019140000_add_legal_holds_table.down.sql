@@ -0,0 +1,2 @@
+-- Down migration
+DROP TABLE legal_holds;
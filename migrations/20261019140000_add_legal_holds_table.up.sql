@@ -0,0 +1,8 @@
+-- Up migration
+-- Guilds whose data must be kept, even if members ask for it to be deleted or Avion is
+-- removed from the guild.
+CREATE TABLE legal_holds (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    reason TEXT NOT NULL,
+    placed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
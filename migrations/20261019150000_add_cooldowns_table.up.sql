@@ -0,0 +1,8 @@
+-- Up migration
+-- Cooldowns that should survive a restart, such as long economy cooldowns.
+CREATE TABLE cooldowns (
+    command TEXT NOT NULL,
+    bucket TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (command, bucket)
+);
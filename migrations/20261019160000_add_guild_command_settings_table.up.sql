@@ -0,0 +1,10 @@
+-- Up migration
+-- Commands a guild has disabled or restricted. Commands without a row are allowed everywhere.
+CREATE TABLE guild_command_settings (
+    guild_id BIGINT NOT NULL,
+    command TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    allowed_channels BIGINT[] NOT NULL DEFAULT '{}',
+    allowed_roles BIGINT[] NOT NULL DEFAULT '{}',
+    PRIMARY KEY (guild_id, command)
+);
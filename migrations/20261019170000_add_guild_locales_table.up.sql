@@ -0,0 +1,6 @@
+-- Up migration
+-- Languages admins have chosen for their guilds with `/settings language`.
+CREATE TABLE guild_locales (
+    guild_id BIGINT PRIMARY KEY,
+    locale TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Up migration
+-- xkcd comic metadata, as returned by `info.0.json`, so comics can be served without xkcd.com.
+CREATE TABLE xkcd_comics (
+    num INTEGER PRIMARY KEY,
+    data JSONB NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,2 @@
+-- Down migration
+ALTER TABLE xkcd_comics DROP COLUMN search;
@@ -0,0 +1,9 @@
+-- Up migration
+-- Full-text search over comics for `/xkcd search`, weighting titles over alt text over transcripts.
+ALTER TABLE xkcd_comics ADD COLUMN search TSVECTOR NOT NULL GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(data->>'safe_title', '')), 'A')
+    || setweight(to_tsvector('english', coalesce(data->>'alt', '')), 'B')
+    || setweight(to_tsvector('english', coalesce(data->>'transcript', '')), 'C')
+) STORED;
+
+CREATE INDEX xkcd_comics_search_idx ON xkcd_comics USING GIN (search);
@@ -0,0 +1,8 @@
+-- Up migration
+-- Channels that get new xkcd comics posted in them, and the last comic each one got.
+CREATE TABLE xkcd_subscriptions (
+    channel_id BIGINT PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    last_posted INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,8 @@
+-- Down migration
+DELETE FROM comic_subscriptions WHERE source <> 'xkcd';
+ALTER TABLE comic_subscriptions DROP CONSTRAINT comic_subscriptions_pkey;
+ALTER TABLE comic_subscriptions DROP COLUMN source;
+ALTER TABLE comic_subscriptions RENAME TO xkcd_subscriptions;
+ALTER TABLE xkcd_subscriptions ADD PRIMARY KEY (channel_id);
+DROP TABLE comic_feed_entries;
+DROP TABLE comic_feeds;
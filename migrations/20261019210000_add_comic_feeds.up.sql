@@ -0,0 +1,26 @@
+-- Up migration
+-- RSS and Atom feeds that guilds follow, with every entry seen so far, numbered in order.
+CREATE TABLE comic_feeds (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (guild_id, name)
+);
+
+CREATE TABLE comic_feed_entries (
+    feed_id INTEGER NOT NULL REFERENCES comic_feeds (id) ON DELETE CASCADE,
+    num INTEGER NOT NULL,
+    entry_id TEXT NOT NULL,
+    data JSONB NOT NULL,
+    PRIMARY KEY (feed_id, num),
+    UNIQUE (feed_id, entry_id)
+);
+
+-- Subscriptions cover any comic source now, identified as `xkcd` or `feed-<id>`
+ALTER TABLE xkcd_subscriptions RENAME TO comic_subscriptions;
+ALTER TABLE comic_subscriptions ADD COLUMN source TEXT NOT NULL DEFAULT 'xkcd';
+ALTER TABLE comic_subscriptions ALTER COLUMN source DROP DEFAULT;
+ALTER TABLE comic_subscriptions DROP CONSTRAINT xkcd_subscriptions_pkey;
+ALTER TABLE comic_subscriptions ADD PRIMARY KEY (channel_id, source);
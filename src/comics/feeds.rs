@@ -0,0 +1,647 @@
+//! RSS and Atom feeds that guild admins add by URL, for web comics besides xkcd.
+//!
+//! Entries are numbered in the order they're first seen and kept in the database, so they can
+//! still be paged through after they drop out of the feed. Feeds are fetched in the
+//! background, so commands and buttons only ever read stored entries.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use poise::serenity_prelude::{Colour, CreateEmbed, CreateEmbedFooter, GuildId, Timestamp};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{error, warn};
+
+use super::{ComicError, ComicProvider, Source};
+use crate::i18n::Locale;
+use crate::metrics::time_query;
+use crate::util::public_http;
+use crate::util::text::truncate;
+
+/// How often to fetch every feed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// The most feeds a guild can add.
+pub const MAX_FEEDS: usize = 10;
+/// The largest feed to download, as admins can point Avion at any URL.
+const MAX_FEED_SIZE: usize = 5 * 1024 * 1024;
+/// Discord's limits on embed titles and descriptions.
+const MAX_TITLE_LENGTH: usize = 256;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feed {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeedEntry {
+    /// Only known once the entry is stored, so it's filled in from the entry's row.
+    #[serde(skip)]
+    pub num: usize,
+    pub title: String,
+    pub link: Option<String>,
+    /// The summary as plain text.
+    pub summary: String,
+    pub image_url: Option<String>,
+    /// When the entry was published, as a Unix timestamp.
+    pub published: Option<i64>,
+}
+
+pub struct Feeds {
+    client: reqwest::Client,
+    db: PgPool,
+    /// Whether feeds can be on private networks, which only tests allow.
+    allow_private: bool,
+}
+
+impl Feeds {
+    pub fn new(timeout: Duration, db: PgPool) -> reqwest::Result<Self> {
+        Ok(Self {
+            client: public_http::client(timeout)?,
+            db,
+            allow_private: false,
+        })
+    }
+
+    /// Adds a feed once it's checked that `url` serves one, storing its current entries.
+    /// Returns `None` if the guild already has a feed called `name`.
+    pub async fn add(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        url: &Url,
+    ) -> Result<Option<Feed>, ComicError> {
+        let entries = self.fetch(url).await?;
+        let id = time_query(
+            "add_comic_feed",
+            sqlx::query_scalar!(
+                "
+                INSERT INTO comic_feeds (guild_id, name, url) VALUES ($1, $2, $3)
+                ON CONFLICT (guild_id, name) DO NOTHING
+                RETURNING id
+                ",
+                i64::from(guild_id),
+                name,
+                url.as_str()
+            )
+            .fetch_optional(&self.db),
+        )
+        .await?;
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        self.store(id, entries).await?;
+        Ok(Some(Feed {
+            id,
+            name: name.to_string(),
+            url: url.to_string(),
+        }))
+    }
+
+    /// Removes a guild's feed along with its entries and subscriptions, returning `false` if
+    /// there was no such feed.
+    pub async fn remove(&self, guild_id: GuildId, name: &str) -> sqlx::Result<bool> {
+        time_query("remove_comic_feed", async {
+            let mut transaction = self.db.begin().await?;
+            let id = sqlx::query_scalar!(
+                "DELETE FROM comic_feeds WHERE guild_id = $1 AND name = $2 RETURNING id",
+                i64::from(guild_id),
+                name
+            )
+            .fetch_optional(&mut *transaction)
+            .await?;
+            let Some(id) = id else {
+                return Ok(false);
+            };
+            sqlx::query!(
+                "DELETE FROM comic_subscriptions WHERE source = $1",
+                Source::Feed(id).to_string()
+            )
+            .execute(&mut *transaction)
+            .await?;
+
+            transaction.commit().await?;
+            Ok(true)
+        })
+        .await
+    }
+
+    /// A guild's feeds, by name.
+    pub async fn list(&self, guild_id: GuildId) -> sqlx::Result<Vec<Feed>> {
+        time_query(
+            "list_comic_feeds",
+            sqlx::query_as!(
+                Feed,
+                "SELECT id, name, url FROM comic_feeds WHERE guild_id = $1 ORDER BY name",
+                i64::from(guild_id)
+            )
+            .fetch_all(&self.db),
+        )
+        .await
+    }
+
+    /// Finds a guild's feed by name.
+    pub async fn find(&self, guild_id: GuildId, name: &str) -> sqlx::Result<Option<Feed>> {
+        time_query(
+            "find_comic_feed",
+            sqlx::query_as!(
+                Feed,
+                "SELECT id, name, url FROM comic_feeds WHERE guild_id = $1 AND name = $2",
+                i64::from(guild_id),
+                name
+            )
+            .fetch_optional(&self.db),
+        )
+        .await
+    }
+
+    pub async fn get(&self, id: i32) -> sqlx::Result<Option<Feed>> {
+        time_query(
+            "get_comic_feed",
+            sqlx::query_as!(
+                Feed,
+                "SELECT id, name, url FROM comic_feeds WHERE id = $1",
+                id
+            )
+            .fetch_optional(&self.db),
+        )
+        .await
+    }
+
+    pub fn provider(&self, feed: Feed) -> FeedProvider<'_> {
+        FeedProvider { feeds: self, feed }
+    }
+
+    /// Fetches new entries from every feed. One feed failing doesn't stop the others.
+    pub async fn refresh_all(&self) -> sqlx::Result<()> {
+        let feeds = time_query(
+            "all_comic_feeds",
+            sqlx::query_as!(Feed, "SELECT id, name, url FROM comic_feeds ORDER BY id")
+                .fetch_all(&self.db),
+        )
+        .await?;
+
+        for feed in feeds {
+            if let Err(err) = self.refresh(&feed).await {
+                warn!(error = %err, feed = feed.id, "Failed to refresh feed");
+            }
+        }
+        Ok(())
+    }
+
+    async fn refresh(&self, feed: &Feed) -> Result<(), ComicError> {
+        let url = Url::parse(&feed.url).map_err(|err| ComicError::InvalidFeed(err.to_string()))?;
+        let entries = self.fetch(&url).await?;
+        self.store(feed.id, entries).await
+    }
+
+    /// Downloads and parses a feed, returning its entries by ID, oldest first.
+    async fn fetch(&self, url: &Url) -> Result<Vec<(String, FeedEntry)>, ComicError> {
+        let too_large = || ComicError::InvalidFeed(format!("larger than {MAX_FEED_SIZE} bytes"));
+        // Checked on every fetch, as the host can start resolving elsewhere after it's added
+        if !self.allow_private && public_http::is_private(url).await {
+            return Err(ComicError::PrivateAddress);
+        }
+
+        let mut response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_FEED_SIZE as u64)
+        {
+            return Err(too_large());
+        }
+        // The length can be left out or wrong, so the body is only read up to the limit
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_FEED_SIZE {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        parse_feed(&body)
+    }
+
+    /// Stores entries that haven't been seen before, numbering them after the last one.
+    async fn store(
+        &self,
+        feed_id: i32,
+        entries: Vec<(String, FeedEntry)>,
+    ) -> Result<(), ComicError> {
+        time_query("store_feed_entries", async {
+            let mut transaction = self.db.begin().await?;
+            for (entry_id, entry) in entries {
+                let data = serde_json::to_value(&entry)
+                    .map_err(|err| ComicError::InvalidFeed(err.to_string()))?;
+                sqlx::query!(
+                    "
+                    INSERT INTO comic_feed_entries (feed_id, num, entry_id, data)
+                    SELECT $1, COALESCE(MAX(num), 0) + 1, $2, $3
+                    FROM comic_feed_entries WHERE feed_id = $1
+                    ON CONFLICT DO NOTHING
+                    ",
+                    feed_id,
+                    entry_id,
+                    data
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
+            transaction.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// One of a guild's feeds, as a source of comics.
+pub struct FeedProvider<'a> {
+    feeds: &'a Feeds,
+    feed: Feed,
+}
+
+impl FeedProvider<'_> {
+    async fn latest_stored(&self) -> Result<Option<FeedEntry>, ComicError> {
+        let record = time_query(
+            "latest_feed_entry",
+            sqlx::query!(
+                "
+                SELECT num, data FROM comic_feed_entries
+                WHERE feed_id = $1 ORDER BY num DESC LIMIT 1
+                ",
+                self.feed.id
+            )
+            .fetch_optional(&self.feeds.db),
+        )
+        .await?;
+
+        record
+            .map(|record| entry_from_row(record.num, record.data))
+            .transpose()
+    }
+}
+
+impl ComicProvider for FeedProvider<'_> {
+    type Comic = FeedEntry;
+
+    fn source(&self) -> Source {
+        Source::Feed(self.feed.id)
+    }
+
+    fn name(&self) -> &str {
+        &self.feed.name
+    }
+
+    /// Gets the newest stored entry. New entries are fetched by [`run_refresh`].
+    async fn latest(&self) -> Result<FeedEntry, ComicError> {
+        self.latest_stored().await?.ok_or(ComicError::NotFound(1))
+    }
+
+    async fn by_id(&self, id: usize) -> Result<FeedEntry, ComicError> {
+        let record = time_query(
+            "get_feed_entry",
+            sqlx::query!(
+                "SELECT num, data FROM comic_feed_entries WHERE feed_id = $1 AND num = $2",
+                self.feed.id,
+                i32::try_from(id).unwrap_or(i32::MAX)
+            )
+            .fetch_optional(&self.feeds.db),
+        )
+        .await?;
+
+        match record {
+            Some(record) => entry_from_row(record.num, record.data),
+            None => Err(ComicError::NotFound(id)),
+        }
+    }
+
+    async fn random(&self) -> Result<FeedEntry, ComicError> {
+        // Makes sure there's something to pick from
+        self.latest().await?;
+        let record = time_query(
+            "random_feed_entry",
+            sqlx::query!(
+                "
+                SELECT num, data FROM comic_feed_entries
+                WHERE feed_id = $1 ORDER BY random() LIMIT 1
+                ",
+                self.feed.id
+            )
+            .fetch_one(&self.feeds.db),
+        )
+        .await?;
+
+        entry_from_row(record.num, record.data)
+    }
+
+    fn id(&self, entry: &FeedEntry) -> usize {
+        entry.num
+    }
+
+    fn into_embed(&self, entry: FeedEntry, _locale: Locale) -> CreateEmbed {
+        let mut embed = CreateEmbed::new()
+            .title(truncate(
+                &format!("#{}: {}", entry.num, entry.title),
+                MAX_TITLE_LENGTH,
+            ))
+            .description(truncate(&entry.summary, MAX_DESCRIPTION_LENGTH))
+            .footer(CreateEmbedFooter::new(&self.feed.name))
+            .colour(Colour::BLUE);
+        if let Some(link) = entry.link {
+            embed = embed.url(link);
+        }
+        if let Some(image_url) = entry.image_url {
+            embed = embed.image(image_url);
+        }
+        if let Some(published) = entry
+            .published
+            .and_then(|secs| Timestamp::from_unix_timestamp(secs).ok())
+        {
+            embed = embed.timestamp(published);
+        }
+
+        embed
+    }
+}
+
+/// Periodically fetches new entries from every feed.
+pub async fn run_refresh(feeds: Arc<Feeds>) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = feeds.refresh_all().await {
+            error!(error = %err, "Failed to refresh feeds");
+        }
+    }
+}
+
+fn entry_from_row(num: i32, data: serde_json::Value) -> Result<FeedEntry, ComicError> {
+    let mut entry: FeedEntry =
+        serde_json::from_value(data).map_err(|err| ComicError::InvalidFeed(err.to_string()))?;
+    entry.num = usize::try_from(num).unwrap_or_default();
+    Ok(entry)
+}
+
+/// Parses an RSS or Atom feed, returning its entries by ID, oldest first.
+fn parse_feed(body: &[u8]) -> Result<Vec<(String, FeedEntry)>, ComicError> {
+    let feed =
+        feed_rs::parser::parse(body).map_err(|err| ComicError::InvalidFeed(err.to_string()))?;
+
+    // Feeds usually list the newest entries first, but go by the dates where there are some
+    let mut entries: Vec<_> = feed.entries.into_iter().rev().map(convert_entry).collect();
+    entries.sort_by_key(|(_, entry)| entry.published);
+    Ok(entries)
+}
+
+fn convert_entry(entry: feed_rs::model::Entry) -> (String, FeedEntry) {
+    let content = entry.content.and_then(|content| content.body);
+    let summary = entry.summary.map(|summary| summary.content);
+    let html = summary
+        .as_deref()
+        .or(content.as_deref())
+        .unwrap_or_default();
+
+    let media_image = entry
+        .media
+        .iter()
+        .flat_map(|media| &media.content)
+        .find(|content| {
+            content
+                .content_type
+                .as_ref()
+                .is_some_and(|content_type| content_type.to_string().starts_with("image/"))
+        })
+        .and_then(|content| content.url.as_ref())
+        .map(ToString::to_string);
+    let image_url = media_image
+        .or_else(|| content.as_deref().and_then(image_in))
+        .or_else(|| summary.as_deref().and_then(image_in));
+
+    let converted = FeedEntry {
+        num: 0,
+        title: plain_text(&entry.title.map(|title| title.content).unwrap_or_default()),
+        link: entry.links.into_iter().next().map(|link| link.href),
+        summary: plain_text(html),
+        image_url,
+        published: entry
+            .published
+            .or(entry.updated)
+            .map(|published| published.timestamp()),
+    };
+    (entry.id, converted)
+}
+
+/// The source of the first image in some HTML, which is how most web comic feeds include
+/// the comic.
+fn image_in(html: &str) -> Option<String> {
+    let tag = &html[html.find("<img")?..];
+    let tag = &tag[..tag.find('>')?];
+    let src = &tag[tag.find("src=")? + 4..];
+    let quote = src.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let src = &src[1..];
+    Some(decode_entities(&src[..src.find(quote)?]))
+}
+
+/// Strips the tags from some HTML, collapsing whitespace.
+fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::Mutex;
+
+    use axum::extract::State;
+    use axum::routing::get;
+    use axum::Router;
+
+    use super::*;
+    use crate::test_utils::{serve, GUILD};
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+        <rss version="2.0"><channel><title>Test Comic</title>
+            <item>
+                <title>Second &amp; last</title>
+                <link>https://example.com/2</link>
+                <guid>2</guid>
+                <pubDate>Tue, 02 Jan 2024 00:00:00 GMT</pubDate>
+                <description>&lt;p&gt;Hello &lt;b&gt;there&lt;/b&gt;&lt;/p&gt;&lt;img src="https://example.com/2.png"&gt;</description>
+            </item>
+            <item>
+                <title>First</title>
+                <link>https://example.com/1</link>
+                <guid>1</guid>
+                <pubDate>Mon, 01 Jan 2024 00:00:00 GMT</pubDate>
+            </item>
+        </channel></rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <feed xmlns="http://www.w3.org/2005/Atom"><title>Test Comic</title><id>feed</id>
+            <updated>2024-01-03T00:00:00Z</updated>
+            <entry>
+                <title>Third</title><id>3</id>
+                <link href="https://example.com/3"/>
+                <updated>2024-01-03T00:00:00Z</updated>
+                <content type="html">&lt;img src='https://example.com/3.png'&gt;</content>
+            </entry>
+        </feed>"#;
+
+    /// Serves whatever feed is in the returned body.
+    async fn stub_server(body: &str) -> (Url, Arc<Mutex<String>>) {
+        let body = Arc::new(Mutex::new(body.to_string()));
+        let app = Router::new()
+            .route(
+                "/feed.xml",
+                get(|State(body): State<Arc<Mutex<String>>>| async move {
+                    body.lock().unwrap().clone()
+                }),
+            )
+            .with_state(body.clone());
+
+        (serve(app).await.join("feed.xml").unwrap(), body)
+    }
+
+    /// Feeds that can fetch from the stub server.
+    fn local_feeds(db: PgPool) -> Feeds {
+        Feeds {
+            allow_private: true,
+            ..Feeds::new(Duration::from_secs(5), db).unwrap()
+        }
+    }
+
+    #[test]
+    fn parses_rss_and_atom() {
+        let entries = parse_feed(RSS.as_bytes()).unwrap();
+        let ids: Vec<_> = entries.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2"]);
+        let (_, second) = &entries[1];
+        assert_eq!(second.title, "Second & last");
+        assert_eq!(second.link.as_deref(), Some("https://example.com/2"));
+        assert_eq!(second.summary, "Hello there");
+        assert_eq!(
+            second.image_url.as_deref(),
+            Some("https://example.com/2.png")
+        );
+        assert_eq!(second.published, Some(1_704_153_600));
+
+        let entries = parse_feed(ATOM.as_bytes()).unwrap();
+        assert_eq!(
+            entries[0].1.image_url.as_deref(),
+            Some("https://example.com/3.png")
+        );
+        assert!(parse_feed(b"<html>not a feed</html>").is_err());
+    }
+
+    #[sqlx::test]
+    async fn numbers_entries_as_they_are_seen(db: PgPool) {
+        let (url, body) = stub_server(RSS).await;
+        let feeds = local_feeds(db);
+        let feed = feeds.add(GUILD, "test", &url).await.unwrap().unwrap();
+        assert!(feeds.add(GUILD, "test", &url).await.unwrap().is_none());
+
+        let provider = feeds.provider(feed.clone());
+        assert_eq!(provider.latest().await.unwrap().title, "Second & last");
+        assert_eq!(provider.by_id(1).await.unwrap().title, "First");
+        assert!(matches!(
+            provider.by_id(3).await,
+            Err(ComicError::NotFound(3))
+        ));
+
+        // The old entries dropping out of the feed doesn't renumber anything
+        *body.lock().unwrap() = ATOM.to_string();
+        feeds.refresh_all().await.unwrap();
+        let latest = provider.latest().await.unwrap();
+        assert_eq!((latest.num, latest.title.as_str()), (3, "Third"));
+        assert_eq!(provider.by_id(1).await.unwrap().title, "First");
+
+        assert_eq!(feeds.list(GUILD).await.unwrap(), vec![feed]);
+        assert!(feeds.remove(GUILD, "test").await.unwrap());
+        assert!(feeds.list(GUILD).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn rejects_urls_that_are_not_feeds(db: PgPool) {
+        let (url, _) = stub_server("<html>not a feed</html>").await;
+        let feeds = local_feeds(db);
+        assert!(matches!(
+            feeds.add(GUILD, "test", &url).await,
+            Err(ComicError::InvalidFeed(_))
+        ));
+        assert!(feeds.list(GUILD).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn rejects_private_addresses(db: PgPool) {
+        let (url, _) = stub_server(RSS).await;
+        let feeds = Feeds::new(Duration::from_secs(5), db).unwrap();
+        assert!(matches!(
+            feeds.add(GUILD, "test", &url).await,
+            Err(ComicError::PrivateAddress)
+        ));
+
+        // Even when the check is skipped, the client won't connect
+        let localhost = format!("http://localhost:{}/feed.xml", url.port().unwrap());
+        assert!(matches!(
+            feeds.client.get(localhost).send().await,
+            Err(err) if err.is_connect()
+        ));
+        assert!(feeds.list(GUILD).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn stops_reading_feeds_that_are_too_large(db: PgPool) {
+        // Sends a body without a length until the client hangs up
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n");
+            let chunk = vec![b'a'; 64 * 1024];
+            while stream.write_all(&chunk).is_ok() {}
+        });
+
+        let feeds = local_feeds(db);
+        assert!(matches!(
+            feeds.add(GUILD, "test", &url.parse().unwrap()).await,
+            Err(ComicError::InvalidFeed(_))
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! Comics from any source behind one interface, so subscriptions and navigation buttons
+//! work the same for xkcd as for the RSS and Atom feeds guilds add.
+
+use std::fmt::{self, Display};
+use std::future::Future;
+
+use poise::serenity_prelude::{Colour, CreateButton, CreateEmbed};
+
+use crate::i18n::{tr, Locale};
+
+pub mod feeds;
+pub mod navigation;
+pub mod subscriptions;
+
+/// Where comics come from, written as `xkcd` or `feed-<id>` in subscriptions and button IDs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Source {
+    Xkcd,
+    Feed(i32),
+}
+
+impl Source {
+    pub fn parse(key: &str) -> Option<Self> {
+        if key == "xkcd" {
+            return Some(Self::Xkcd);
+        }
+        key.strip_prefix("feed-")?.parse().ok().map(Self::Feed)
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xkcd => write!(f, "xkcd"),
+            Self::Feed(id) => write!(f, "feed-{id}"),
+        }
+    }
+}
+
+/// A source of numbered comics, numbered from 1.
+pub trait ComicProvider: Send + Sync {
+    type Comic: Send;
+
+    fn source(&self) -> Source;
+
+    /// What to call the source in messages.
+    fn name(&self) -> &str;
+
+    fn latest(&self) -> impl Future<Output = Result<Self::Comic, ComicError>> + Send;
+
+    fn by_id(&self, id: usize) -> impl Future<Output = Result<Self::Comic, ComicError>> + Send;
+
+    fn random(&self) -> impl Future<Output = Result<Self::Comic, ComicError>> + Send;
+
+    fn id(&self, comic: &Self::Comic) -> usize;
+
+    /// Shows `comic`, consuming it like the comics' own `into_embed`s.
+    #[allow(clippy::wrong_self_convention)]
+    fn into_embed(&self, comic: Self::Comic, locale: Locale) -> CreateEmbed;
+
+    /// Whether there's deliberately no comic with this ID, so paging and posting skip it.
+    fn is_missing(&self, _id: usize) -> bool {
+        false
+    }
+
+    /// Link buttons to show under a comic, after the navigation buttons.
+    fn links(&self, _comic: &Self::Comic, _locale: Locale) -> Vec<CreateButton> {
+        Vec::new()
+    }
+
+    fn error_embed(&self, err: ComicError, locale: Locale) -> CreateEmbed {
+        err.into_embed(self.name(), locale)
+    }
+}
+
+#[derive(Debug)]
+pub enum ComicError {
+    /// There's no comic with this number.
+    NotFound(usize),
+    /// The source didn't respond in time.
+    Timeout,
+    /// The source couldn't be reached, or responded with an error or something that isn't a
+    /// comic.
+    Upstream(reqwest::Error),
+    /// A feed couldn't be read as RSS or Atom.
+    InvalidFeed(String),
+    /// A feed is on a private network, which Avion won't connect to.
+    PrivateAddress,
+    Database(sqlx::Error),
+}
+
+impl ComicError {
+    /// Whether trying again might help.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::NotFound(_) | Self::InvalidFeed(_) | Self::PrivateAddress | Self::Database(_) => {
+                false
+            }
+            Self::Timeout => true,
+            Self::Upstream(err) => {
+                err.is_connect() || err.status().is_some_and(|status| status.is_server_error())
+            }
+        }
+    }
+
+    /// The outcome to record in request metrics.
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::Timeout => "timeout",
+            Self::Upstream(_) | Self::InvalidFeed(_) | Self::PrivateAddress | Self::Database(_) => {
+                "error"
+            }
+        }
+    }
+
+    /// Explains the error, calling the comic's source `name`.
+    pub fn into_embed(self, name: &str, locale: Locale) -> CreateEmbed {
+        let (title, description) = match self {
+            Self::NotFound(_) => (
+                tr!(locale, "comic-not-found-title"),
+                tr!(locale, "comic-not-found-description"),
+            ),
+            Self::Timeout => (
+                tr!(locale, "comic-unavailable-title", source = name),
+                tr!(locale, "comic-timeout-description", source = name),
+            ),
+            Self::Upstream(_) | Self::Database(_) => (
+                tr!(locale, "comic-unavailable-title", source = name),
+                tr!(locale, "comic-upstream-description", source = name),
+            ),
+            Self::InvalidFeed(_) => (
+                tr!(locale, "comic-unavailable-title", source = name),
+                tr!(locale, "comic-invalid-feed-description", source = name),
+            ),
+            Self::PrivateAddress => (
+                tr!(locale, "comic-unavailable-title", source = name),
+                tr!(locale, "comic-private-address-description", source = name),
+            ),
+        };
+
+        CreateEmbed::new()
+            .title(title)
+            .description(description)
+            .colour(Colour::RED)
+    }
+}
+
+impl Display for ComicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(num) => write!(f, "comic {num} does not exist"),
+            Self::Timeout => write!(f, "request timed out"),
+            Self::Upstream(err) => write!(f, "request failed: {err}"),
+            Self::InvalidFeed(reason) => write!(f, "invalid feed: {reason}"),
+            Self::PrivateAddress => write!(f, "feed is on a private network"),
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ComicError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Upstream(err) => Some(err),
+            Self::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ComicError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Upstream(err)
+        }
+    }
+}
+
+impl From<sqlx::Error> for ComicError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Database(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_round_trip() {
+        for source in [Source::Xkcd, Source::Feed(12)] {
+            assert_eq!(Source::parse(&source.to_string()), Some(source));
+        }
+        assert_eq!(Source::parse("feed-"), None);
+        assert_eq!(Source::parse("xkcd_search"), None);
+    }
+}
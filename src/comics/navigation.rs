@@ -0,0 +1,262 @@
+//! Buttons under comics to page through them, which edit the message in place.
+//!
+//! The buttons carry the comic's source and the comic they lead to in their custom ID, like
+//! `xkcd:927`, so they keep working after a restart, and on comics posted to subscribed
+//! channels.
+
+use std::future::Future;
+
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, Colour, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    EditInteractionResponse,
+};
+use tracing::warn;
+
+use super::{ComicError, ComicProvider, Source};
+use crate::i18n::{self, tr, Locale};
+use crate::{Context, Data, Error};
+
+const RANDOM: &str = "random";
+
+/// Previous, Random and Next buttons for `comic`, followed by the provider's links.
+pub fn buttons<P: ComicProvider>(
+    provider: &P,
+    comic: &P::Comic,
+    latest: usize,
+    locale: Locale,
+) -> Vec<CreateActionRow> {
+    let source = provider.source();
+    let id = provider.id(comic);
+    let mut previous = id - 1;
+    while previous > 0 && provider.is_missing(previous) {
+        previous -= 1;
+    }
+    let mut next = id + 1;
+    while next <= latest && provider.is_missing(next) {
+        next += 1;
+    }
+
+    let mut buttons = vec![
+        CreateButton::new(format!("{source}:{previous}"))
+            .label(tr!(locale, "comic-button-previous"))
+            .style(ButtonStyle::Secondary)
+            .disabled(previous == 0),
+        CreateButton::new(format!("{source}:{RANDOM}"))
+            .label(tr!(locale, "comic-button-random"))
+            .style(ButtonStyle::Primary),
+        CreateButton::new(format!("{source}:{next}"))
+            .label(tr!(locale, "comic-button-next"))
+            .style(ButtonStyle::Secondary)
+            .disabled(next > latest),
+    ];
+    buttons.extend(provider.links(comic, locale));
+    vec![CreateActionRow::Buttons(buttons)]
+}
+
+/// Replies with `comic` and buttons to page through the provider's comics, or explains why
+/// it couldn't be fetched.
+pub async fn send<P: ComicProvider>(
+    ctx: Context<'_>,
+    provider: &P,
+    comic: impl Future<Output = Result<P::Comic, ComicError>>,
+) -> Result<(), Error> {
+    // Fetching can take longer than Discord waits for a response
+    ctx.defer().await?;
+    let reply = reply(provider, comic.await, i18n::locale(ctx)).await?;
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+/// A reply showing `comic` with buttons, or explaining why it couldn't be fetched.
+pub async fn reply<P: ComicProvider>(
+    provider: &P,
+    comic: Result<P::Comic, ComicError>,
+    locale: Locale,
+) -> Result<poise::CreateReply, Error> {
+    Ok(match comic {
+        Ok(comic) => {
+            let latest = provider.id(&provider.latest().await?);
+            poise::CreateReply::default()
+                .components(buttons(provider, &comic, latest, locale))
+                .embed(provider.into_embed(comic, locale))
+        }
+        Err(err) => {
+            log_error(provider, &err);
+            poise::CreateReply::default()
+                .components(vec![])
+                .embed(provider.error_embed(err, locale))
+        }
+    })
+}
+
+/// Handles a press of one of the buttons, ignoring other components, which are awaited by
+/// the commands that sent them.
+pub async fn handle(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+) -> Result<(), Error> {
+    let Some((source, target)) = interaction
+        .data
+        .custom_id
+        .split_once(':')
+        .and_then(|(source, target)| Some((Source::parse(source)?, target)))
+    else {
+        return Ok(());
+    };
+    let locale = i18n::resolve(data, interaction.guild_id, Some(&interaction.locale));
+    // Fetching can take longer than Discord waits, so the message is edited afterwards
+    interaction
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    let response = match source {
+        Source::Xkcd => respond(&*data.xkcd, target, locale).await?,
+        Source::Feed(id) => match data.feeds.get(id).await? {
+            Some(feed) => respond(&data.feeds.provider(feed), target, locale).await?,
+            // The feed was removed since the comic was posted
+            None => Err(CreateEmbed::new()
+                .title(tr!(locale, "comic-feed-removed-title"))
+                .description(tr!(locale, "comic-feed-removed-description"))
+                .colour(Colour::RED)),
+        },
+    };
+    match response {
+        Ok(edit) => {
+            interaction.edit_response(ctx, edit).await?;
+        }
+        Err(embed) => {
+            interaction
+                .create_followup(
+                    ctx,
+                    CreateInteractionResponseFollowup::new()
+                        .embed(embed)
+                        .ephemeral(true),
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// An edit showing the comic the button leads to, or an error to show the presser.
+async fn respond<P: ComicProvider>(
+    provider: &P,
+    target: &str,
+    locale: Locale,
+) -> Result<Result<EditInteractionResponse, CreateEmbed>, Error> {
+    let comic = if target == RANDOM {
+        provider.random().await
+    } else {
+        // Only Avion makes these IDs, so this is always a number
+        provider.by_id(target.parse().unwrap_or_default()).await
+    };
+
+    Ok(match comic {
+        Ok(comic) => {
+            let latest = provider.id(&provider.latest().await?);
+            Ok(EditInteractionResponse::new()
+                .components(buttons(provider, &comic, latest, locale))
+                .embed(provider.into_embed(comic, locale)))
+        }
+        Err(err) => {
+            log_error(provider, &err);
+            Err(provider.error_embed(err, locale))
+        }
+    })
+}
+
+/// Logs failures other than comics not existing, which are the user's mistake.
+fn log_error<P: ComicProvider>(provider: &P, err: &ComicError) {
+    if !matches!(err, ComicError::NotFound(_)) {
+        warn!(error = %err, source = %provider.source(), "Failed to fetch comic");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Stands in for xkcd, whose comics are their own numbers.
+    struct Provider;
+
+    impl ComicProvider for Provider {
+        type Comic = usize;
+
+        fn source(&self) -> Source {
+            Source::Xkcd
+        }
+
+        fn name(&self) -> &'static str {
+            "test"
+        }
+
+        async fn latest(&self) -> Result<usize, ComicError> {
+            unreachable!()
+        }
+
+        async fn by_id(&self, _: usize) -> Result<usize, ComicError> {
+            unreachable!()
+        }
+
+        async fn random(&self) -> Result<usize, ComicError> {
+            unreachable!()
+        }
+
+        fn id(&self, comic: &usize) -> usize {
+            *comic
+        }
+
+        fn into_embed(&self, _: usize, _: Locale) -> serenity::CreateEmbed {
+            unreachable!()
+        }
+
+        fn is_missing(&self, id: usize) -> bool {
+            id == crate::xkcd::MISSING_COMIC
+        }
+    }
+
+    /// The custom IDs of the buttons, and whether each is enabled, leaving out links.
+    fn targets(num: usize, latest: usize) -> Vec<(String, bool)> {
+        let row =
+            serde_json::to_value(&buttons(&Provider, &num, latest, Locale::ENGLISH)[0]).unwrap();
+        row["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|button| {
+                let id = button["custom_id"].as_str()?.to_string();
+                Some((id, button["disabled"] != Value::Bool(true)))
+            })
+            .collect()
+    }
+
+    fn target(id: &str, enabled: bool) -> (String, bool) {
+        (id.to_string(), enabled)
+    }
+
+    #[test]
+    fn pages_between_neighbours() {
+        assert_eq!(
+            targets(10, 20),
+            vec![
+                target("xkcd:9", true),
+                target("xkcd:random", true),
+                target("xkcd:11", true)
+            ]
+        );
+        assert_eq!(targets(403, 500)[2], target("xkcd:405", true));
+        assert_eq!(targets(405, 500)[0], target("xkcd:403", true));
+    }
+
+    #[test]
+    fn stops_at_the_first_and_latest_comics() {
+        assert_eq!(targets(1, 20)[0], target("xkcd:0", false));
+        assert_eq!(targets(20, 20)[2], target("xkcd:21", false));
+    }
+}
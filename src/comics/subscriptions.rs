@@ -0,0 +1,313 @@
+//! Channels that get new comics posted in them as they come out, from any source.
+//!
+//! Each subscription remembers the last comic posted to it, so comics published while Avion
+//! was down are posted when it's back, and nothing is posted twice.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use poise::serenity_prelude::{self as serenity, ChannelId, CreateMessage, GuildId, Http};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+use super::feeds::Feeds;
+use super::{navigation, ComicError, ComicProvider, Source};
+use crate::config::FeaturesConfig;
+use crate::i18n::Locale;
+use crate::metrics::time_query;
+use crate::xkcd::XkcdClient;
+use crate::Error;
+
+/// How often to check for new comics. Sources are only asked once their TTL is up.
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// The most comics to post to a subscription per poll, so one that has fallen far behind
+/// catches up over a few polls instead of flooding its channel.
+const MAX_CATCH_UP: usize = 5;
+
+/// Subscribes a channel to a source, starting after the `latest` comic. Returns `false` if
+/// the channel was already subscribed to it.
+pub async fn subscribe(
+    db: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    source: Source,
+    latest: usize,
+) -> Result<bool, Error> {
+    let result = time_query(
+        "subscribe_comics",
+        sqlx::query!(
+            "
+            INSERT INTO comic_subscriptions (guild_id, channel_id, source, last_posted)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (channel_id, source) DO NOTHING
+            ",
+            i64::from(guild_id),
+            i64::from(channel_id),
+            source.to_string(),
+            i32::try_from(latest)?
+        )
+        .execute(db),
+    )
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Unsubscribes a channel from a source, returning `false` if it wasn't subscribed.
+pub async fn unsubscribe(db: &PgPool, channel_id: ChannelId, source: Source) -> sqlx::Result<bool> {
+    let result = time_query(
+        "unsubscribe_comics",
+        sqlx::query!(
+            "DELETE FROM comic_subscriptions WHERE channel_id = $1 AND source = $2",
+            i64::from(channel_id),
+            source.to_string()
+        )
+        .execute(db),
+    )
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Unsubscribes a deleted channel from every source.
+async fn unsubscribe_all(db: &PgPool, channel_id: ChannelId) -> sqlx::Result<()> {
+    time_query(
+        "unsubscribe_channel",
+        sqlx::query!(
+            "DELETE FROM comic_subscriptions WHERE channel_id = $1",
+            i64::from(channel_id)
+        )
+        .execute(db),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Every source with at least one subscription.
+async fn subscribed_sources(db: &PgPool) -> sqlx::Result<Vec<Source>> {
+    let sources = time_query(
+        "subscribed_comic_sources",
+        sqlx::query_scalar!("SELECT DISTINCT source FROM comic_subscriptions ORDER BY source")
+            .fetch_all(db),
+    )
+    .await?;
+
+    Ok(sources
+        .iter()
+        .filter_map(|source| Source::parse(source))
+        .collect())
+}
+
+/// Subscriptions to `source` that haven't had the `latest` comic yet, with the last comic
+/// they had.
+async fn behind(
+    db: &PgPool,
+    source: Source,
+    latest: usize,
+) -> Result<Vec<(ChannelId, usize)>, Error> {
+    let records = time_query(
+        "comic_subscriptions_behind",
+        sqlx::query!(
+            "
+            SELECT channel_id, last_posted FROM comic_subscriptions
+            WHERE source = $1 AND last_posted < $2
+            ",
+            source.to_string(),
+            i32::try_from(latest)?
+        )
+        .fetch_all(db),
+    )
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| {
+            let channel_id = ChannelId::new(u64::try_from(record.channel_id).ok()?);
+            Some((channel_id, usize::try_from(record.last_posted).ok()?))
+        })
+        .collect())
+}
+
+async fn mark_posted(
+    db: &PgPool,
+    channel_id: ChannelId,
+    source: Source,
+    num: usize,
+) -> Result<(), Error> {
+    time_query(
+        "mark_comic_posted",
+        sqlx::query!(
+            "
+            UPDATE comic_subscriptions SET last_posted = $3
+            WHERE channel_id = $1 AND source = $2
+            ",
+            i64::from(channel_id),
+            source.to_string(),
+            i32::try_from(num)?
+        )
+        .execute(db),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// The comics to post to a subscription this poll, oldest first. Subscriptions that are far
+/// behind get the oldest few, and the rest on later polls.
+fn comics_to_post(
+    last_posted: usize,
+    latest: usize,
+    is_missing: impl Fn(usize) -> bool,
+) -> Vec<usize> {
+    (last_posted + 1..=latest)
+        .filter(|&num| !is_missing(num))
+        .take(MAX_CATCH_UP)
+        .collect()
+}
+
+/// Posts new comics from `provider` to every subscription to it that's behind.
+async fn post_new_comics<P: ComicProvider>(
+    http: &Http,
+    provider: &P,
+    db: &PgPool,
+) -> Result<(), Error> {
+    let source = provider.source();
+    let latest = provider.id(&provider.latest().await?);
+    for (channel_id, last_posted) in behind(db, source, latest).await? {
+        for num in comics_to_post(last_posted, latest, |num| provider.is_missing(num)) {
+            let comic = match provider.by_id(num).await {
+                Ok(comic) => comic,
+                // Feeds can skip numbers if an entry couldn't be stored, so move past them
+                Err(ComicError::NotFound(_)) => {
+                    mark_posted(db, channel_id, source, num).await?;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            // There's no one to take a language from, and comics are mostly pictures anyway
+            let message = CreateMessage::new()
+                .components(navigation::buttons(
+                    provider,
+                    &comic,
+                    latest,
+                    Locale::default(),
+                ))
+                .embed(provider.into_embed(comic, Locale::default()));
+            match channel_id.send_message(http, message).await {
+                Ok(_) => mark_posted(db, channel_id, source, num).await?,
+                Err(serenity::Error::Http(err))
+                    if err
+                        .status_code()
+                        .is_some_and(|status| status.as_u16() == 404) =>
+                {
+                    info!(%channel_id, "Subscribed channel was deleted, unsubscribing");
+                    unsubscribe_all(db, channel_id).await?;
+                    break;
+                }
+                // Try again next time
+                Err(err) => {
+                    warn!(error = %err, %channel_id, "Failed to post comic");
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Posts new comics from every subscribed source whose feature is enabled. Subscriptions
+/// to disabled sources are kept, and catch up once they're enabled again.
+pub async fn post_all_new_comics(
+    http: &Http,
+    xkcd: &XkcdClient,
+    feeds: &Feeds,
+    db: &PgPool,
+    features: &FeaturesConfig,
+) -> Result<(), Error> {
+    for source in subscribed_sources(db).await? {
+        let result = match source {
+            Source::Xkcd if !features.xkcd => continue,
+            Source::Feed(_) if !features.feeds => continue,
+            Source::Xkcd => post_new_comics(http, xkcd, db).await,
+            Source::Feed(id) => match feeds.get(id).await? {
+                Some(feed) => post_new_comics(http, &feeds.provider(feed), db).await,
+                None => Ok(()),
+            },
+        };
+        // One source being down shouldn't hold up the others
+        if let Err(err) = result {
+            warn!(error = %err, %source, "Failed to post new comics");
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically posts new comics to subscribed channels.
+pub async fn run_poller(
+    http: Arc<Http>,
+    xkcd: Arc<XkcdClient>,
+    feeds: Arc<Feeds>,
+    db: PgPool,
+    features: FeaturesConfig,
+) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = post_all_new_comics(&http, &xkcd, &feeds, &db, &features).await {
+            error!(error = %err, "Failed to post new comics");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::GUILD;
+    use crate::xkcd::MISSING_COMIC;
+
+    const CHANNEL: ChannelId = ChannelId::new(100);
+
+    #[test]
+    fn catches_up_on_missed_comics() {
+        let is_missing = |num| num == MISSING_COMIC;
+        assert!(comics_to_post(10, 10, is_missing).is_empty());
+        assert_eq!(comics_to_post(10, 12, is_missing), vec![11, 12]);
+        assert_eq!(comics_to_post(402, 405, is_missing), vec![403, 405]);
+        // Far behind subscriptions catch up a few comics at a time, without skipping any
+        assert_eq!(comics_to_post(1, 100, is_missing), vec![2, 3, 4, 5, 6]);
+        assert_eq!(comics_to_post(6, 100, is_missing), vec![7, 8, 9, 10, 11]);
+        assert_eq!(
+            comics_to_post(400, 410, is_missing),
+            vec![401, 402, 403, 405, 406]
+        );
+    }
+
+    #[sqlx::test]
+    async fn tracks_the_last_posted_comic(db: PgPool) {
+        let feed = Source::Feed(1);
+        assert!(subscribe(&db, GUILD, CHANNEL, Source::Xkcd, 10)
+            .await
+            .unwrap());
+        assert!(!subscribe(&db, GUILD, CHANNEL, Source::Xkcd, 11)
+            .await
+            .unwrap());
+        assert!(subscribe(&db, GUILD, CHANNEL, feed, 3).await.unwrap());
+        assert_eq!(behind(&db, Source::Xkcd, 10).await.unwrap(), vec![]);
+        assert_eq!(
+            behind(&db, Source::Xkcd, 11).await.unwrap(),
+            vec![(CHANNEL, 10)]
+        );
+
+        mark_posted(&db, CHANNEL, Source::Xkcd, 11).await.unwrap();
+        assert_eq!(behind(&db, Source::Xkcd, 11).await.unwrap(), vec![]);
+        assert_eq!(behind(&db, feed, 4).await.unwrap(), vec![(CHANNEL, 3)]);
+
+        assert!(unsubscribe(&db, CHANNEL, Source::Xkcd).await.unwrap());
+        assert!(!unsubscribe(&db, CHANNEL, Source::Xkcd).await.unwrap());
+        assert_eq!(behind(&db, Source::Xkcd, 12).await.unwrap(), vec![]);
+        assert_eq!(subscribed_sources(&db).await.unwrap(), vec![feed]);
+    }
+}
@@ -0,0 +1,325 @@
+//! Per-guild settings that disable a command or restrict it to some channels or roles.
+//!
+//! Settings apply to top-level commands, and so to all of their subcommands. Members who can
+//! manage the server are never restricted, so they can't lock themselves out.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use poise::serenity_prelude::{ChannelId, Colour, CreateEmbed, GuildId, Permissions, RoleId};
+use sqlx::PgPool;
+
+use crate::i18n::{tr, Locale};
+use crate::metrics::time_query;
+use crate::{Context, Error};
+
+/// The command that edits settings, which can't be restricted.
+pub const SETTINGS_COMMAND: &str = "settings";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandSettings {
+    pub enabled: bool,
+    /// Channels the command can be used in. Empty means any channel.
+    pub channels: Vec<ChannelId>,
+    /// Roles allowed to use the command. Empty means anyone.
+    pub roles: Vec<RoleId>,
+}
+
+impl Default for CommandSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            channels: Vec::new(),
+            roles: Vec::new(),
+        }
+    }
+}
+
+/// Why a command can't be used.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Denial {
+    Disabled,
+    WrongChannel(Vec<ChannelId>),
+    MissingRole(Vec<RoleId>),
+}
+
+impl CommandSettings {
+    pub fn check(&self, channel_id: ChannelId, member_roles: &[RoleId]) -> Result<(), Denial> {
+        if !self.enabled {
+            return Err(Denial::Disabled);
+        }
+        if !self.channels.is_empty() && !self.channels.contains(&channel_id) {
+            return Err(Denial::WrongChannel(self.channels.clone()));
+        }
+        if !self.roles.is_empty() && !self.roles.iter().any(|role| member_roles.contains(role)) {
+            return Err(Denial::MissingRole(self.roles.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl Denial {
+    pub fn into_embed(self, locale: Locale) -> CreateEmbed {
+        let mentions = |mentions: Vec<String>| mentions.join(", ");
+        let description = match self {
+            Self::Disabled => tr!(locale, "command-disabled"),
+            Self::WrongChannel(channels) => tr!(
+                locale,
+                "command-wrong-channel",
+                channels = mentions(channels.iter().map(|id| format!("<#{id}>")).collect())
+            ),
+            Self::MissingRole(roles) => tr!(
+                locale,
+                "command-missing-role",
+                roles = mentions(roles.iter().map(|id| format!("<@&{id}>")).collect())
+            ),
+        };
+
+        CreateEmbed::new()
+            .title(tr!(locale, "command-unavailable-title"))
+            .description(description)
+            .colour(Colour::RED)
+    }
+}
+
+/// Checks the invoked command against the guild's settings.
+pub async fn check(ctx: Context<'_>) -> Result<Result<(), Denial>, Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(Ok(()));
+    };
+    let command = ctx
+        .command()
+        .qualified_name
+        .split(' ')
+        .next()
+        .unwrap_or_default();
+    if command == SETTINGS_COMMAND {
+        return Ok(Ok(()));
+    }
+
+    let Some(member) = ctx.author_member().await else {
+        return Ok(Ok(()));
+    };
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD))
+    {
+        return Ok(Ok(()));
+    }
+
+    let settings = ctx.data().command_settings.get(guild_id, command);
+    Ok(settings.check(ctx.channel_id(), &member.roles))
+}
+
+fn to_ids(ids: &[impl Into<i64> + Copy]) -> Vec<i64> {
+    ids.iter().map(|&id| id.into()).collect()
+}
+
+fn from_ids<T: From<u64>>(ids: Vec<i64>) -> Vec<T> {
+    ids.into_iter()
+        .filter_map(|id| u64::try_from(id).ok())
+        .filter(|&id| id != 0)
+        .map(T::from)
+        .collect()
+}
+
+/// Every guild's settings, kept in memory since they're checked for every command.
+#[derive(Default)]
+pub struct GuildCommandSettings {
+    settings: RwLock<HashMap<GuildId, HashMap<String, CommandSettings>>>,
+}
+
+impl GuildCommandSettings {
+    pub async fn load(db: &PgPool) -> sqlx::Result<Self> {
+        let records = time_query(
+            "load_command_settings",
+            sqlx::query!(
+                "
+                SELECT guild_id, command, enabled, allowed_channels, allowed_roles
+                FROM guild_command_settings
+                "
+            )
+            .fetch_all(db),
+        )
+        .await?;
+
+        let mut settings: HashMap<_, HashMap<_, _>> = HashMap::new();
+        for record in records {
+            let Some(guild_id) = u64::try_from(record.guild_id).ok().filter(|&id| id != 0) else {
+                continue;
+            };
+            settings.entry(GuildId::new(guild_id)).or_default().insert(
+                record.command,
+                CommandSettings {
+                    enabled: record.enabled,
+                    channels: from_ids(record.allowed_channels),
+                    roles: from_ids(record.allowed_roles),
+                },
+            );
+        }
+        Ok(Self {
+            settings: RwLock::new(settings),
+        })
+    }
+
+    pub fn get(&self, guild_id: GuildId, command: &str) -> CommandSettings {
+        self.settings
+            .read()
+            .unwrap()
+            .get(&guild_id)
+            .and_then(|commands| commands.get(command))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Lists every command with settings in the guild.
+    pub fn list(&self, guild_id: GuildId) -> Vec<(String, CommandSettings)> {
+        let mut list: Vec<_> = self
+            .settings
+            .read()
+            .unwrap()
+            .get(&guild_id)
+            .map(|commands| {
+                commands
+                    .iter()
+                    .map(|(command, settings)| (command.clone(), settings.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        list.sort_by(|(a, _), (b, _)| a.cmp(b));
+        list
+    }
+
+    /// Saves the settings, removing them entirely if they're back to the defaults.
+    pub async fn save(
+        &self,
+        db: &PgPool,
+        guild_id: GuildId,
+        command: &str,
+        settings: &CommandSettings,
+    ) -> sqlx::Result<()> {
+        if *settings == CommandSettings::default() {
+            time_query(
+                "reset_command_settings",
+                sqlx::query!(
+                    "DELETE FROM guild_command_settings WHERE guild_id = $1 AND command = $2",
+                    i64::from(guild_id),
+                    command
+                )
+                .execute(db),
+            )
+            .await?;
+            let mut guilds = self.settings.write().unwrap();
+            if let Some(commands) = guilds.get_mut(&guild_id) {
+                commands.remove(command);
+                if commands.is_empty() {
+                    guilds.remove(&guild_id);
+                }
+            }
+            return Ok(());
+        }
+
+        time_query(
+            "save_command_settings",
+            sqlx::query!(
+                "
+                INSERT INTO guild_command_settings
+                    (guild_id, command, enabled, allowed_channels, allowed_roles)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (guild_id, command) DO UPDATE SET
+                    enabled = EXCLUDED.enabled,
+                    allowed_channels = EXCLUDED.allowed_channels,
+                    allowed_roles = EXCLUDED.allowed_roles
+                ",
+                i64::from(guild_id),
+                command,
+                settings.enabled,
+                &to_ids(&settings.channels),
+                &to_ids(&settings.roles)
+            )
+            .execute(db),
+        )
+        .await?;
+        self.settings
+            .write()
+            .unwrap()
+            .entry(guild_id)
+            .or_default()
+            .insert(command.to_string(), settings.clone());
+
+        Ok(())
+    }
+
+    /// Drops the settings of guilds whose data was purged.
+    pub fn forget(&self, guild_ids: &[GuildId]) {
+        let mut guilds = self.settings.write().unwrap();
+        for guild_id in guild_ids {
+            guilds.remove(guild_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::GUILD;
+
+    const CHANNEL: ChannelId = ChannelId::new(100);
+    const ROLE: RoleId = RoleId::new(200);
+
+    #[test]
+    fn checks_channels_and_roles() {
+        let settings = CommandSettings {
+            channels: vec![CHANNEL],
+            roles: vec![ROLE],
+            ..CommandSettings::default()
+        };
+
+        assert_eq!(settings.check(CHANNEL, &[RoleId::new(1), ROLE]), Ok(()));
+        assert_eq!(
+            settings.check(ChannelId::new(1), &[ROLE]),
+            Err(Denial::WrongChannel(vec![CHANNEL]))
+        );
+        assert_eq!(
+            settings.check(CHANNEL, &[]),
+            Err(Denial::MissingRole(vec![ROLE]))
+        );
+        assert_eq!(
+            CommandSettings {
+                enabled: false,
+                ..CommandSettings::default()
+            }
+            .check(CHANNEL, &[]),
+            Err(Denial::Disabled)
+        );
+    }
+
+    #[sqlx::test]
+    async fn saves_and_resets_settings(db: PgPool) {
+        let cache = GuildCommandSettings::load(&db).await.unwrap();
+        assert_eq!(cache.get(GUILD, "xkcd"), CommandSettings::default());
+
+        let settings = CommandSettings {
+            enabled: false,
+            channels: vec![CHANNEL],
+            roles: vec![ROLE],
+        };
+        cache.save(&db, GUILD, "xkcd", &settings).await.unwrap();
+        assert_eq!(cache.get(GUILD, "xkcd"), settings);
+        assert_eq!(
+            cache.get(GuildId::new(2), "xkcd"),
+            CommandSettings::default()
+        );
+        // Saved settings are loaded back on startup
+        let reloaded = GuildCommandSettings::load(&db).await.unwrap();
+        assert_eq!(reloaded.list(GUILD), vec![("xkcd".to_string(), settings)]);
+
+        cache
+            .save(&db, GUILD, "xkcd", &CommandSettings::default())
+            .await
+            .unwrap();
+        assert_eq!(cache.list(GUILD), vec![]);
+        let reloaded = GuildCommandSettings::load(&db).await.unwrap();
+        assert_eq!(reloaded.list(GUILD), vec![]);
+    }
+}
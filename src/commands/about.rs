@@ -1,28 +1,63 @@
 use poise::serenity_prelude::{Colour, CreateEmbed, CreateEmbedFooter, Timestamp};
 
-use crate::util::image_urls;
+use crate::util::db::PoolStats;
+use crate::util::{build_info, image_urls};
 use crate::util::timestamp::{Format as TimestampFormat, TimestampExt};
+use crate::i18n::{self, tr};
 use crate::{Context, Error};
 
 /// Information about Avion
 #[poise::command(slash_command)]
 pub async fn about(ctx: Context<'_>) -> Result<(), Error> {
     // TODO: finish the command
-    let build_timestamp = Timestamp::parse(env!("VERGEN_BUILD_TIMESTAMP"))?
+    let locale = i18n::locale(ctx);
+    let build_timestamp = Timestamp::parse(build_info::BUILD_TIMESTAMP)?
         .to_discord_timestamp(TimestampFormat::LongDateShortTime);
+    let (database_pool, database_wait) = match PoolStats::sample(&ctx.data().db).await {
+        Ok(stats) => (
+            tr!(
+                locale,
+                "about-pool-value",
+                active = stats.active,
+                idle = stats.idle,
+                max = stats.max
+            ),
+            format!("{:.1?}", stats.acquire_wait),
+        ),
+        Err(_) => (tr!(locale, "about-unavailable"), "N/A".to_string()),
+    };
+    let shard = tr!(
+        locale,
+        "about-shard-value",
+        id = ctx.serenity_context().shard_id.0,
+        total = ctx.cache().shard_count()
+    );
+    let gateway_latency = format!("{:.0?}", ctx.ping().await);
 
     let embed = CreateEmbed::default()
-        .title("About Avion")
-        .field("Version", env!("CARGO_PKG_VERSION"), true)
-        .field("Build timestamp", build_timestamp, true)
+        .title(tr!(locale, "about-title"))
+        .field(tr!(locale, "about-version"), build_info::VERSION, true)
+        .field(tr!(locale, "about-build-timestamp"), build_timestamp, true)
         .field("", "", false)
-        .field("Rust version", env!("VERGEN_RUSTC_SEMVER"), true)
-        .field("Git commit", format!("`{}`", env!("VERGEN_GIT_SHA")), true)
+        .field(
+            tr!(locale, "about-rust-version"),
+            build_info::RUSTC_SEMVER,
+            true,
+        )
+        .field(
+            tr!(locale, "about-git-commit"),
+            format!("`{}`", build_info::GIT_SHA),
+            true,
+        )
+        .field("", "", false)
+        .field(tr!(locale, "about-pool"), database_pool, true)
+        .field(tr!(locale, "about-connection-wait"), database_wait, true)
+        .field("", "", false)
+        .field(tr!(locale, "about-shard"), shard, true)
+        .field(tr!(locale, "about-gateway-latency"), gateway_latency, true)
         .thumbnail(image_urls::AVION_AVATAR)
         .colour(Colour::BLUE)
-        .footer(CreateEmbedFooter::new(
-            "Third-party licenses: TODO (ask @justhypex)",
-        ));
+        .footer(CreateEmbedFooter::new(tr!(locale, "about-footer")));
     ctx.send(poise::CreateReply::default().embed(embed)).await?;
 
     Ok(())
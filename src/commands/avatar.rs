@@ -1,8 +1,13 @@
+use super::user_info::fetch_member;
+use crate::i18n::{self, tr};
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
-use serenity::Colour;
+use serenity::{Colour, CreateActionRow, CreateButton};
 
-#[poise::command(slash_command, subcommands("user"))]
+/// The image sizes to link to, in pixels.
+const SIZES: [u16; 3] = [256, 1024, 4096];
+
+#[poise::command(slash_command, subcommands("user", "server"))]
 #[allow(clippy::unused_async)]
 pub async fn avatar(_: Context<'_>) -> Result<(), Error> {
     unreachable!()
@@ -16,12 +21,171 @@ pub async fn user(
 ) -> Result<(), Error> {
     let u = user.as_ref().unwrap_or_else(|| ctx.author());
 
-    let embed = serenity::CreateEmbed::new()
-        .title(format!("@{username}'s avatar", username = u.name))
-        .image(u.face())
-        .colour(Colour::BLUE);
+    let title = tr!(
+        i18n::locale(ctx),
+        "avatar-title",
+        username = u.name.as_str()
+    );
+    ctx.send(image_reply(title, &u.face(), Colour::BLUE))
+        .await?;
+
+    Ok(())
+}
+
+/// Gets a member's avatar in this server.
+#[poise::command(slash_command, guild_only)]
+pub async fn server(
+    ctx: Context<'_>,
+    #[description = "Selected user - defaults to you"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let u = user.as_ref().unwrap_or_else(|| ctx.author());
+    let locale = i18n::locale(ctx);
+    let username = u.name.as_str();
+
+    let Some(member) = fetch_member(ctx, ctx.guild_id().unwrap(), u.id).await? else {
+        let embed = serenity::CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(tr!(locale, "avatar-not-member", username = username))
+            .colour(Colour::RED);
+        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    };
 
-    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    let title = tr!(locale, "avatar-server-title", username = username);
+    let mut reply = image_reply(title, &member.face(), Colour::BLUE);
+    if member.avatar.is_none() {
+        reply = reply.content(tr!(locale, "avatar-no-server-avatar", username = username));
+    }
+    ctx.send(reply).await?;
 
     Ok(())
 }
+
+/// Gets a user's avatar, as shown in this server
+#[poise::command(context_menu_command = "View Avatar", rename = "avatar-menu")]
+pub async fn avatar_menu(ctx: Context<'_>, user: serenity::User) -> Result<(), Error> {
+    send_face(ctx, &user).await
+}
+
+/// Gets a message author's avatar, as shown in this server
+#[poise::command(context_menu_command = "View Author's Avatar", rename = "avatar-message-menu")]
+pub async fn avatar_message_menu(
+    ctx: Context<'_>,
+    message: serenity::Message,
+) -> Result<(), Error> {
+    send_face(ctx, &message.author).await
+}
+
+/// Replies with the avatar `u` has where the command was used.
+async fn send_face(ctx: Context<'_>, u: &serenity::User) -> Result<(), Error> {
+    let member = match ctx.guild_id() {
+        Some(guild_id) => fetch_member(ctx, guild_id, u.id).await?,
+        None => None,
+    };
+    let face = member.map_or_else(|| u.face(), |member| member.face());
+
+    let title = tr!(
+        i18n::locale(ctx),
+        "avatar-title",
+        username = u.name.as_str()
+    );
+    ctx.send(image_reply(title, &face, Colour::BLUE)).await?;
+
+    Ok(())
+}
+
+/// A reply showing the image at `url`, with buttons to download it in each format and size.
+pub fn image_reply(title: String, url: &str, colour: Colour) -> poise::CreateReply {
+    let embed = serenity::CreateEmbed::new()
+        .title(title)
+        .image(url)
+        .colour(colour);
+    let rows = download_links(url)
+        .into_iter()
+        .map(|links| {
+            CreateActionRow::Buttons(
+                links
+                    .into_iter()
+                    .map(|(label, link)| CreateButton::new_link(link).label(label))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    poise::CreateReply::default().embed(embed).components(rows)
+}
+
+/// Labels and links for a Discord CDN image in every format and size it comes in, with a
+/// row for each format.
+fn download_links(url: &str) -> Vec<Vec<(String, String)>> {
+    let path = url.split('?').next().unwrap_or_default();
+    let Some((base, extension)) = path.rsplit_once('.') else {
+        return Vec::new();
+    };
+    // Default avatars only come as PNGs, in one size
+    if base.contains("/embed/avatars/") {
+        return vec![vec![("PNG".to_string(), path.to_string())]];
+    }
+
+    let mut formats = vec!["png", "jpg", "webp"];
+    // Discord links animated images as GIFs
+    if extension == "gif" {
+        formats.push("gif");
+    }
+    formats
+        .into_iter()
+        .map(|format| {
+            SIZES
+                .iter()
+                .map(|size| {
+                    (
+                        format!("{} {size}px", format.to_uppercase()),
+                        format!("{base}.{format}?size={size}"),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AVATAR: &str = "https://cdn.discordapp.com/avatars/1/abc.webp?size=1024";
+
+    #[test]
+    fn links_every_format_and_size() {
+        let links = download_links(AVATAR);
+        assert_eq!(links.len(), 3);
+        assert_eq!(
+            links[0][0],
+            (
+                "PNG 256px".to_string(),
+                "https://cdn.discordapp.com/avatars/1/abc.png?size=256".to_string()
+            )
+        );
+        assert_eq!(
+            links[2][2].1,
+            "https://cdn.discordapp.com/avatars/1/abc.webp?size=4096"
+        );
+    }
+
+    #[test]
+    fn links_gifs_for_animated_images() {
+        let links = download_links("https://cdn.discordapp.com/avatars/1/a_abc.gif?size=1024");
+        assert_eq!(
+            links[3][1].1,
+            "https://cdn.discordapp.com/avatars/1/a_abc.gif?size=1024"
+        );
+    }
+
+    #[test]
+    fn links_default_avatars_as_they_are() {
+        let url = "https://cdn.discordapp.com/embed/avatars/3.png";
+        assert_eq!(
+            download_links(url),
+            vec![vec![("PNG".to_string(), url.to_string())]]
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use crate::{embeds, util::db::UserBalances};
+use crate::economy::{EconomyError, EconomyService, Store};
+use crate::embeds;
+use crate::i18n::{self, tr, Locale};
 use crate::{Context, Error};
-use poise::serenity_prelude::{Colour, CreateEmbed, CreateEmbedAuthor, User};
+use poise::serenity_prelude::{Colour, CreateEmbed, CreateEmbedAuthor, GuildId, User, UserId};
 
 /// Gets a user's balance in the server.
 #[poise::command(slash_command, guild_only)]
@@ -16,26 +18,89 @@ pub async fn balance(
         .to_partial_guild(&ctx.http())
         .await?;
     let guild_icon_url = guild.icon_url().unwrap_or_default();
-    let db = &ctx.data().db;
-
-    let balances = match UserBalances::from_user_and_guild_ids(u.id, guild.id, db).await {
-        Ok(record) => record,
-        Err(sqlx::Error::RowNotFound) => {
-            ctx.send(poise::CreateReply::default().embed(embeds::user_not_in_db()))
-                .await?;
-            return Ok(());
-        }
-        Err(err) => return Err(Box::new(err)),
-    };
-
-    let embed = CreateEmbed::new()
-        .title(format!("@{username}'s balances", username = u.name))
-        .field("Wallet Balance", balances.wallet_balance.to_string(), true)
-        .field("Bank Balance", balances.bank_balance.to_string(), true)
-        .author(CreateEmbedAuthor::new(guild.name).icon_url(guild_icon_url))
-        .colour(Colour::BLUE);
 
+    let embed = balance_embed(
+        &ctx.data().economy,
+        i18n::locale(ctx),
+        u.id,
+        &u.name,
+        guild.id,
+    )
+    .await?
+    .author(CreateEmbedAuthor::new(guild.name).icon_url(guild_icon_url));
     ctx.send(poise::CreateReply::default().embed(embed)).await?;
 
     Ok(())
 }
+
+async fn balance_embed(
+    economy: &EconomyService<impl Store>,
+    locale: Locale,
+    user_id: UserId,
+    username: &str,
+    guild_id: GuildId,
+) -> Result<CreateEmbed, EconomyError> {
+    let balances = match economy.get_balances(guild_id, user_id).await {
+        Ok(balances) => balances,
+        Err(EconomyError::NotRegistered(_)) => return Ok(embeds::user_not_in_db(locale)),
+        Err(err) => return Err(err),
+    };
+
+    Ok(CreateEmbed::new()
+        .title(tr!(locale, "balance-title", username = username))
+        .field(
+            tr!(locale, "balance-wallet"),
+            balances.wallet.to_string(),
+            true,
+        )
+        .field(tr!(locale, "balance-bank"), balances.bank.to_string(), true)
+        .colour(Colour::BLUE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::{Balances, MemoryStore};
+    use crate::test_utils::{GUILD, USER};
+
+    const EN: Locale = Locale::ENGLISH;
+
+    #[tokio::test]
+    async fn unregistered_user_is_not_found() {
+        let economy = EconomyService::new(MemoryStore::default());
+
+        let embed = balance_embed(&economy, EN, USER, "someone", GUILD)
+            .await
+            .unwrap();
+        assert_eq!(embed, embeds::user_not_in_db(EN));
+    }
+
+    #[tokio::test]
+    async fn shows_wallet_and_bank() {
+        let store = MemoryStore::default();
+        store.set(GUILD, USER, Balances { wallet: 25, bank: 100 });
+        let economy = EconomyService::new(store);
+
+        let embed = balance_embed(&economy, EN, USER, "someone", GUILD)
+            .await
+            .unwrap();
+        let expected = CreateEmbed::new()
+            .title("@someone's balances")
+            .field("Wallet Balance", "25", true)
+            .field("Bank Balance", "100", true)
+            .colour(Colour::BLUE);
+        assert_eq!(embed, expected);
+    }
+
+    #[tokio::test]
+    async fn balances_are_per_guild() {
+        let store = MemoryStore::default();
+        store.set(GuildId::new(2), USER, Balances { wallet: 25, bank: 100 });
+        let economy = EconomyService::new(store);
+
+        let embed = balance_embed(&economy, EN, USER, "someone", GUILD)
+            .await
+            .unwrap();
+        assert_eq!(embed, embeds::user_not_in_db(EN));
+    }
+}
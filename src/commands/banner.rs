@@ -0,0 +1,36 @@
+use super::avatar::image_reply;
+use crate::i18n::{self, tr};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use serenity::Colour;
+
+/// Gets a user's profile banner.
+#[poise::command(slash_command)]
+pub async fn banner(
+    ctx: Context<'_>,
+    #[description = "Selected user - defaults to you"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let id = user.as_ref().map_or_else(|| ctx.author().id, |u| u.id);
+    let locale = i18n::locale(ctx);
+    // Users in interactions don't come with their banner
+    let u = ctx.http().get_user(id).await?;
+    let username = u.name.as_str();
+    let colour = u.accent_colour.unwrap_or(Colour::BLUE);
+
+    let reply = if let Some(url) = u.banner_url() {
+        image_reply(
+            tr!(locale, "banner-title", username = username),
+            &url,
+            colour,
+        )
+    } else {
+        let embed = serenity::CreateEmbed::new()
+            .title(tr!(locale, "banner-title", username = username))
+            .description(tr!(locale, "banner-none", username = username))
+            .colour(colour);
+        poise::CreateReply::default().embed(embed)
+    };
+    ctx.send(reply).await?;
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use crate::i18n::{self, tr, Locale};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use serenity::{Colour, GuildChannel, GuildId, PermissionOverwrite, PermissionOverwriteType};
+
+use crate::util::text::truncate;
+use crate::util::timestamp::{Format as TimestampFormat, TimestampExt};
+
+/// The most permission overwrites to list before summing up the rest.
+const MAX_OVERWRITES: usize = 10;
+/// The longest an embed field's value can be.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Displays a channel's type, topic, slowmode, permission overwrites, and creation date
+#[poise::command(slash_command, guild_only, rename = "channelinfo")]
+pub async fn channel_info(
+    ctx: Context<'_>,
+    #[description = "The channel to show - defaults to this one"] channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let channel = match channel {
+        Some(channel) => channel,
+        None => ctx
+            .channel_id()
+            .to_channel(ctx)
+            .await?
+            .guild()
+            .ok_or("command was used outside a guild channel")?,
+    };
+
+    let created_at = channel.id.created_at();
+    let created = format!(
+        "{} ({})",
+        created_at.to_discord_timestamp(TimestampFormat::LongDate),
+        created_at.to_discord_timestamp(TimestampFormat::Relative)
+    );
+    let topic = channel.topic.as_deref().filter(|topic| !topic.is_empty());
+    let slowmode = match channel.rate_limit_per_user {
+        Some(seconds) if seconds > 0 => duration(seconds),
+        _ => tr!(locale, "channelinfo-slowmode-off"),
+    };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("#{}", channel.name))
+        .colour(Colour::BLUE)
+        .fields(vec![
+            (
+                tr!(locale, "channelinfo-type"),
+                tr!(
+                    locale,
+                    "channelinfo-type-name",
+                    kind = u8::from(channel.kind)
+                ),
+                true,
+            ),
+            (tr!(locale, "channelinfo-created"), created, true),
+        ])
+        .field("", "", false)
+        .fields(vec![
+            (tr!(locale, "channelinfo-slowmode"), slowmode, true),
+            (
+                tr!(locale, "channelinfo-category"),
+                channel.parent_id.map_or_else(
+                    || tr!(locale, "userinfo-none"),
+                    |parent_id| format!("<#{parent_id}>"),
+                ),
+                true,
+            ),
+        ])
+        .field(
+            tr!(locale, "channelinfo-topic"),
+            topic.map_or_else(
+                || tr!(locale, "userinfo-none"),
+                |topic| truncate(topic, MAX_FIELD_LENGTH),
+            ),
+            false,
+        )
+        .field(
+            tr!(
+                locale,
+                "channelinfo-overwrites",
+                count = channel.permission_overwrites.len()
+            ),
+            overwrite_summary(locale, channel.guild_id, &channel.permission_overwrites),
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            locale,
+            "channelinfo-id",
+            id = channel.id.to_string()
+        )));
+    if channel.nsfw {
+        embed = embed.description(tr!(locale, "channelinfo-nsfw"));
+    }
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// A line for each role or member with overwritten permissions, with how many permissions
+/// are allowed and denied.
+fn overwrite_summary(
+    locale: Locale,
+    guild_id: GuildId,
+    overwrites: &[PermissionOverwrite],
+) -> String {
+    if overwrites.is_empty() {
+        return tr!(locale, "userinfo-none");
+    }
+
+    let mut summary = String::new();
+    for overwrite in overwrites.iter().take(MAX_OVERWRITES) {
+        let target = match overwrite.kind {
+            // The @everyone role shares the server's ID, and doesn't mention nicely
+            PermissionOverwriteType::Role(role_id) if role_id.get() == guild_id.get() => {
+                "@everyone".to_string()
+            }
+            PermissionOverwriteType::Role(role_id) => format!("<@&{role_id}>"),
+            PermissionOverwriteType::Member(user_id) => format!("<@{user_id}>"),
+            _ => continue,
+        };
+        let _ = writeln!(
+            summary,
+            "{}",
+            tr!(
+                locale,
+                "channelinfo-overwrite",
+                target = target,
+                allowed = overwrite.allow.bits().count_ones(),
+                denied = overwrite.deny.bits().count_ones()
+            )
+        );
+    }
+    if overwrites.len() > MAX_OVERWRITES {
+        summary.push_str(&tr!(
+            locale,
+            "channelinfo-more-overwrites",
+            count = overwrites.len() - MAX_OVERWRITES
+        ));
+    }
+    summary
+}
+
+/// Formats a slowmode like `1h 30m`, leaving out empty units.
+fn duration(seconds: u16) -> String {
+    let units = [(seconds / 3600, "h"), (seconds / 60 % 60, "m"), (seconds % 60, "s")];
+    units
+        .iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::{Permissions, RoleId, UserId};
+
+    use crate::test_utils::GUILD;
+
+    const EN: Locale = Locale::ENGLISH;
+
+    #[test]
+    fn formats_slowmodes() {
+        assert_eq!(duration(5), "5s");
+        assert_eq!(duration(90), "1m 30s");
+        assert_eq!(duration(21600), "6h");
+    }
+
+    #[test]
+    fn summarises_overwrites() {
+        let overwrites = [
+            PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::SEND_MESSAGES,
+                kind: PermissionOverwriteType::Role(RoleId::new(1)),
+            },
+            PermissionOverwrite {
+                allow: Permissions::SEND_MESSAGES | Permissions::EMBED_LINKS,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(UserId::new(2)),
+            },
+        ];
+        assert_eq!(
+            overwrite_summary(EN, GUILD, &overwrites),
+            "@everyone: 0 allowed, 1 denied\n<@2>: 2 allowed, 0 denied\n"
+        );
+        assert_eq!(overwrite_summary(EN, GUILD, &[]), "None");
+    }
+}
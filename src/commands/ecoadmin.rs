@@ -0,0 +1,311 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+use serenity::{
+    Attachment, ButtonStyle, Colour, CreateActionRow, CreateAttachment, CreateButton, CreateEmbed,
+};
+
+use crate::economy::backup::{self, Backup, Diff};
+use crate::embeds;
+use crate::i18n::{self, tr, Locale};
+use crate::{Context, Error};
+
+/// Imports larger than this are refused without downloading them.
+const MAX_IMPORT_SIZE: u32 = 8 * 1024 * 1024;
+/// How many changed accounts to list in an import preview.
+const PREVIEW_LINES: usize = 10;
+
+#[derive(poise::ChoiceParameter)]
+pub enum Format {
+    #[name = "JSON"]
+    Json,
+    #[name = "CSV"]
+    Csv,
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("export", "import"),
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD"
+)]
+#[allow(clippy::unused_async)]
+pub async fn ecoadmin(_: Context<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+/// Export this server's balances and jobs.
+#[poise::command(slash_command, guild_only)]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "File format - defaults to JSON"] format: Option<Format>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let locale = i18n::locale(ctx);
+    let guild_id = ctx.guild_id().unwrap();
+    let backup = backup::export(&ctx.data().db, guild_id).await?;
+
+    let mut reply = poise::CreateReply::default().ephemeral(true).embed(
+        CreateEmbed::new()
+            .title(tr!(locale, "ecoadmin-export-title"))
+            .description(tr!(
+                locale,
+                "ecoadmin-export-description",
+                accounts = backup.users.len(),
+                jobs = backup.jobs.len()
+            ))
+            .colour(Colour::BLUE),
+    );
+    match format.unwrap_or(Format::Json) {
+        Format::Json => {
+            reply = reply.attachment(CreateAttachment::bytes(
+                backup.to_json()?,
+                format!("avion-{guild_id}.json"),
+            ));
+        }
+        Format::Csv => {
+            let (users, jobs) = backup.to_csv()?;
+            reply = reply
+                .attachment(CreateAttachment::bytes(
+                    users,
+                    format!("avion-{guild_id}-users.csv"),
+                ))
+                .attachment(CreateAttachment::bytes(
+                    jobs,
+                    format!("avion-{guild_id}-jobs.csv"),
+                ));
+        }
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+/// Import balances or jobs from an Avion export, or another bot's CSV or JSON export.
+#[poise::command(slash_command, guild_only)]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "A .json or .csv file"] file: Attachment,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let locale = i18n::locale(ctx);
+    let guild_id = ctx.guild_id().unwrap();
+    let db = &ctx.data().db;
+
+    let import = match read_import(&file, locale).await? {
+        Ok(import) => import,
+        Err(message) => {
+            let embed = CreateEmbed::new()
+                .title(tr!(locale, "ecoadmin-import-unreadable"))
+                .description(message)
+                .colour(Colour::RED);
+            ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let diff = backup::diff(&backup::export(db, guild_id).await?, &import);
+    if diff.is_empty() {
+        let embed = CreateEmbed::new()
+            .title(tr!(locale, "ecoadmin-import-nothing-title"))
+            .description(tr!(locale, "ecoadmin-import-nothing-description"))
+            .colour(Colour::BLUE);
+        ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        return Ok(());
+    }
+
+    let components = vec![CreateActionRow::Buttons(vec![
+        CreateButton::new("confirm_import")
+            .label(tr!(locale, "ecoadmin-import-confirm"))
+            .style(ButtonStyle::Danger),
+        CreateButton::new("cancel_import")
+            .label(tr!(locale, "cancel"))
+            .style(ButtonStyle::Secondary),
+    ])];
+    let reply_handle = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(preview_embed(&diff, locale))
+                .components(components)
+                .ephemeral(true),
+        )
+        .await?;
+    let m = reply_handle.message().await?;
+
+    let interaction = tokio::select! {
+        interaction = m
+            .await_component_interaction(&ctx.serenity_context().shard)
+            .timeout(Duration::from_secs(60 * 3))
+            .author_id(ctx.author().id) => interaction,
+        () = ctx.data().shutdown.triggered() => None,
+    };
+
+    let embed = match interaction {
+        Some(interaction) if interaction.data.custom_id == "confirm_import" => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            backup::import(db, guild_id, &import).await?;
+            CreateEmbed::new()
+                .title(tr!(locale, "success-title"))
+                .description(tr!(
+                    locale,
+                    "ecoadmin-import-success",
+                    accounts = import.users.len(),
+                    jobs = import.jobs.len()
+                ))
+                .colour(Colour::DARK_TEAL)
+        }
+        Some(interaction) => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            CreateEmbed::new()
+                .title(tr!(locale, "cancelled-title"))
+                .description(tr!(locale, "ecoadmin-import-nothing-imported"))
+                .colour(Colour::BLUE)
+        }
+        None if ctx.data().shutdown.is_triggered() => embeds::bot_restarting(locale),
+        None => CreateEmbed::new()
+            .title(tr!(locale, "timed-out"))
+            .description(tr!(locale, "ecoadmin-import-nothing-imported"))
+            .colour(Colour::BLUE),
+    };
+    reply_handle
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(embed)
+                .components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Downloads and parses an import, returning why it can't be read if it's invalid.
+async fn read_import(file: &Attachment, locale: Locale) -> Result<Result<Backup, String>, Error> {
+    if file.size > MAX_IMPORT_SIZE {
+        return Ok(Err(tr!(locale, "ecoadmin-import-too-large")));
+    }
+
+    let bytes = file.download().await?;
+    let extension = file.filename.rsplit('.').next().unwrap_or_default();
+    Ok(match extension.to_ascii_lowercase().as_str() {
+        "json" => Backup::from_json(&bytes).map_err(|err| err.to_string()),
+        "csv" => Backup::from_csv(&bytes).map_err(|err| err.to_string()),
+        _ => Err(tr!(locale, "ecoadmin-import-wrong-type")),
+    })
+}
+
+fn preview_embed(diff: &Diff, locale: Locale) -> CreateEmbed {
+    let mut description = String::new();
+    let changes = diff
+        .added
+        .iter()
+        .map(|(user_id, new)| (user_id, None, new))
+        .chain(
+            diff.changed
+                .iter()
+                .map(|(user_id, old, new)| (user_id, Some(old), new)),
+        );
+    for (user_id, old, new) in changes.clone().take(PREVIEW_LINES) {
+        let line = if let Some(old) = old {
+            tr!(
+                locale,
+                "ecoadmin-preview-changed",
+                user = format!("<@{user_id}>"),
+                old_wallet = old.wallet,
+                wallet = new.wallet,
+                old_bank = old.bank,
+                bank = new.bank
+            )
+        } else {
+            tr!(
+                locale,
+                "ecoadmin-preview-added",
+                user = format!("<@{user_id}>"),
+                wallet = new.wallet,
+                bank = new.bank
+            )
+        };
+        let _ = writeln!(description, "{line}");
+    }
+    let remaining = changes.count().saturating_sub(PREVIEW_LINES);
+    if remaining > 0 {
+        let _ = writeln!(
+            description,
+            "{}",
+            tr!(locale, "ecoadmin-preview-more", remaining = remaining)
+        );
+    }
+
+    CreateEmbed::new()
+        .title(tr!(locale, "ecoadmin-preview-title"))
+        .description(description)
+        .field(
+            tr!(locale, "ecoadmin-preview-new-accounts"),
+            diff.added.len().to_string(),
+            true,
+        )
+        .field(
+            tr!(locale, "ecoadmin-preview-changed-accounts"),
+            diff.changed.len().to_string(),
+            true,
+        )
+        .field(
+            tr!(locale, "ecoadmin-preview-reassigned-accounts"),
+            diff.reassigned.to_string(),
+            true,
+        )
+        .field(
+            tr!(locale, "ecoadmin-preview-unchanged-accounts"),
+            diff.unchanged.to_string(),
+            true,
+        )
+        .field(
+            tr!(locale, "ecoadmin-preview-jobs"),
+            tr!(
+                locale,
+                "ecoadmin-preview-jobs-value",
+                added = diff.jobs_added,
+                changed = diff.jobs_changed
+            ),
+            true,
+        )
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            locale,
+            "ecoadmin-preview-footer"
+        )))
+        .colour(Colour::GOLD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::Balances;
+    use crate::test_utils::{OTHER_USER, USER};
+
+    #[test]
+    fn preview_lists_changes() {
+        let diff = Diff {
+            added: vec![(OTHER_USER, Balances { wallet: 5, bank: 0 })],
+            changed: vec![(
+                USER,
+                Balances { wallet: 10, bank: 0 },
+                Balances { wallet: 20, bank: 1 },
+            )],
+            ..Diff::default()
+        };
+
+        let embed = serde_json::to_value(preview_embed(&diff, Locale::ENGLISH)).unwrap();
+        assert_eq!(
+            embed["description"],
+            "<@20>: new, wallet 5, bank 0\n<@10>: wallet 10 → 20, bank 0 → 1\n"
+        );
+    }
+}
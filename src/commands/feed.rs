@@ -0,0 +1,312 @@
+use std::fmt::Write;
+
+use crate::comics::feeds::{FeedProvider, MAX_FEEDS};
+use crate::comics::{navigation, subscriptions, ComicProvider};
+use crate::i18n::{self, tr};
+use crate::{Context, Error};
+
+use poise::serenity_prelude::{AutocompleteChoice, Colour, CreateEmbed, GuildChannel};
+use reqwest::Url;
+
+/// The longest feed name, so names fit in autocomplete choices.
+const MAX_NAME_LENGTH: usize = 100;
+
+#[poise::command(
+    slash_command,
+    subcommands(
+        "add",
+        "remove",
+        "list",
+        "latest",
+        "random",
+        "comic",
+        "subscribe",
+        "unsubscribe"
+    )
+)]
+#[allow(clippy::unused_async)]
+pub async fn feed(_: Context<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+async fn autocomplete_feed(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Vec::new();
+    };
+    let feeds = ctx.data().feeds.list(guild_id).await.unwrap_or_default();
+    let partial = partial.to_lowercase();
+    feeds
+        .into_iter()
+        .filter(|feed| feed.name.to_lowercase().contains(&partial))
+        .map(|feed| AutocompleteChoice::new(feed.name.clone(), feed.name))
+        .collect()
+}
+
+/// Finds the guild's feed called `name`, telling the user if there's none.
+async fn find_feed<'a>(ctx: Context<'a>, name: &str) -> Result<Option<FeedProvider<'a>>, Error> {
+    let feeds = &ctx.data().feeds;
+    if let Some(feed) = feeds.find(ctx.guild_id().unwrap(), name).await? {
+        return Ok(Some(feeds.provider(feed)));
+    }
+
+    let locale = i18n::locale(ctx);
+    let embed = CreateEmbed::new()
+        .title(tr!(locale, "feed-not-found-title"))
+        .description(tr!(locale, "feed-not-found-description", name = name))
+        .colour(Colour::RED);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(None)
+}
+
+/// Add an RSS or Atom feed of a web comic to this server.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "What to call the feed"] name: String,
+    #[description = "The feed's address"] url: String,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let guild_id = ctx.guild_id().unwrap();
+    let name = name.trim();
+    let error = |description: String| {
+        CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(description)
+            .colour(Colour::RED)
+    };
+
+    let url = Url::parse(&url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"));
+    let embed = if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        error(tr!(locale, "feed-invalid-name", max = MAX_NAME_LENGTH))
+    } else if url.is_none() {
+        error(tr!(locale, "feed-invalid-url"))
+    } else if ctx.data().feeds.list(guild_id).await?.len() >= MAX_FEEDS {
+        error(tr!(locale, "feed-too-many", max = MAX_FEEDS))
+    } else {
+        // Fetching the feed can take a while
+        ctx.defer().await?;
+        match ctx.data().feeds.add(guild_id, name, &url.unwrap()).await {
+            Ok(Some(_)) => CreateEmbed::new()
+                .title(tr!(locale, "success-title"))
+                .description(tr!(locale, "feed-added", name = name))
+                .colour(Colour::DARK_TEAL),
+            Ok(None) => error(tr!(locale, "feed-name-taken", name = name)),
+            Err(err) => err.into_embed(name, locale),
+        }
+    };
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove a feed from this server, along with its subscriptions.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The feed to remove"]
+    #[autocomplete = "autocomplete_feed"]
+    feed: String,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let removed = ctx
+        .data()
+        .feeds
+        .remove(ctx.guild_id().unwrap(), &feed)
+        .await?;
+
+    let embed = if removed {
+        CreateEmbed::new()
+            .title(tr!(locale, "success-title"))
+            .description(tr!(locale, "feed-removed", name = feed.as_str()))
+            .colour(Colour::DARK_TEAL)
+    } else {
+        CreateEmbed::new()
+            .title(tr!(locale, "feed-not-found-title"))
+            .description(tr!(
+                locale,
+                "feed-not-found-description",
+                name = feed.as_str()
+            ))
+            .colour(Colour::RED)
+    };
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// List this server's feeds.
+#[poise::command(slash_command, guild_only)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let feeds = ctx.data().feeds.list(ctx.guild_id().unwrap()).await?;
+
+    let mut description = String::new();
+    for feed in &feeds {
+        let _ = writeln!(description, "**{}** - <{}>", feed.name, feed.url);
+    }
+    if feeds.is_empty() {
+        description = tr!(locale, "feed-list-empty");
+    }
+    let embed = CreateEmbed::new()
+        .title(tr!(locale, "feed-list-title"))
+        .description(description)
+        .colour(Colour::BLUE);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Get the latest comic from a feed.
+#[poise::command(slash_command, guild_only)]
+pub async fn latest(
+    ctx: Context<'_>,
+    #[description = "The feed to read"]
+    #[autocomplete = "autocomplete_feed"]
+    feed: String,
+) -> Result<(), Error> {
+    if let Some(provider) = find_feed(ctx, &feed).await? {
+        navigation::send(ctx, &provider, provider.latest()).await?;
+    }
+
+    Ok(())
+}
+
+/// Get a random comic from a feed.
+#[poise::command(slash_command, guild_only)]
+pub async fn random(
+    ctx: Context<'_>,
+    #[description = "The feed to read"]
+    #[autocomplete = "autocomplete_feed"]
+    feed: String,
+) -> Result<(), Error> {
+    if let Some(provider) = find_feed(ctx, &feed).await? {
+        navigation::send(ctx, &provider, provider.random()).await?;
+    }
+
+    Ok(())
+}
+
+/// Get a specific comic from a feed, numbered from the first one Avion saw.
+#[poise::command(slash_command, guild_only)]
+pub async fn comic(
+    ctx: Context<'_>,
+    #[description = "The feed to read"]
+    #[autocomplete = "autocomplete_feed"]
+    feed: String,
+    #[description = "Comic number"] num: usize,
+) -> Result<(), Error> {
+    if let Some(provider) = find_feed(ctx, &feed).await? {
+        navigation::send(ctx, &provider, provider.by_id(num)).await?;
+    }
+
+    Ok(())
+}
+
+/// Post a feed's new comics in a channel as they come out.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn subscribe(
+    ctx: Context<'_>,
+    #[description = "The feed to follow"]
+    #[autocomplete = "autocomplete_feed"]
+    feed: String,
+    #[description = "The channel to post in - defaults to this one"]
+    #[channel_types("Text", "News")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let Some(provider) = find_feed(ctx, &feed).await? else {
+        return Ok(());
+    };
+    let locale = i18n::locale(ctx);
+    let channel_id = channel.map_or_else(|| ctx.channel_id(), |channel| channel.id);
+    let latest = match provider.latest().await {
+        Ok(entry) => provider.id(&entry),
+        Err(err) => {
+            ctx.send(poise::CreateReply::default().embed(provider.error_embed(err, locale)))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let subscribed = subscriptions::subscribe(
+        &ctx.data().db,
+        ctx.guild_id().unwrap(),
+        channel_id,
+        provider.source(),
+        latest,
+    )
+    .await?;
+    let channel = format!("<#{channel_id}>");
+    let embed = if subscribed {
+        CreateEmbed::new()
+            .title(tr!(locale, "success-title"))
+            .description(tr!(
+                locale,
+                "feed-subscribed",
+                name = provider.name(),
+                channel = channel
+            ))
+            .colour(Colour::DARK_TEAL)
+    } else {
+        CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(tr!(
+                locale,
+                "feed-already-subscribed",
+                name = provider.name(),
+                channel = channel
+            ))
+            .colour(Colour::RED)
+    };
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Stop posting a feed's new comics in a channel.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn unsubscribe(
+    ctx: Context<'_>,
+    #[description = "The feed to stop following"]
+    #[autocomplete = "autocomplete_feed"]
+    feed: String,
+    #[description = "The channel to stop posting in - defaults to this one"]
+    #[channel_types("Text", "News")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let Some(provider) = find_feed(ctx, &feed).await? else {
+        return Ok(());
+    };
+    let locale = i18n::locale(ctx);
+    let channel_id = channel.map_or_else(|| ctx.channel_id(), |channel| channel.id);
+
+    let unsubscribed =
+        subscriptions::unsubscribe(&ctx.data().db, channel_id, provider.source()).await?;
+    let channel = format!("<#{channel_id}>");
+    let embed = if unsubscribed {
+        CreateEmbed::new()
+            .title(tr!(locale, "success-title"))
+            .description(tr!(
+                locale,
+                "feed-unsubscribed",
+                name = provider.name(),
+                channel = channel
+            ))
+            .colour(Colour::DARK_TEAL)
+    } else {
+        CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(tr!(
+                locale,
+                "feed-not-subscribed",
+                name = provider.name(),
+                channel = channel
+            ))
+            .colour(Colour::RED)
+    };
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
@@ -6,7 +6,9 @@ use serenity::{
     CreateEmbedAuthor, GuildId, UserId,
 };
 
-use crate::{embeds, util::db::UserBalances};
+use crate::economy::{EconomyError, EconomyService, Store, Transfer};
+use crate::embeds;
+use crate::i18n::{self, tr, Locale};
 use crate::{Context, Error};
 
 enum UserSelection {
@@ -25,17 +27,7 @@ pub async fn give(
     amount: i32,
 ) -> Result<(), Error> {
     let giver = ctx.author();
-    if receiver.bot {
-        ctx.send(poise::CreateReply::default().embed(embeds::bots_not_allowed()))
-            .await?;
-        return Ok(());
-    }
-    if receiver.id == giver.id {
-        ctx.send(poise::CreateReply::default().embed(embeds::cannot_use_yourself()))
-            .await?;
-        return Ok(());
-    }
-
+    let locale = i18n::locale(ctx);
     let guild = ctx
         .guild_id()
         .ok_or("Guild ID not found")?
@@ -44,64 +36,49 @@ pub async fn give(
     let guild_icon_url = guild
         .icon_url()
         .unwrap_or_else(|| String::from("Default Icon URL")); // TODO: fix this
-    let db = &ctx.data().db;
+    let guild_author = CreateEmbedAuthor::new(guild.name).icon_url(guild_icon_url);
+    let economy = &ctx.data().economy;
 
-    // TODO: I don't like this code. At the same time, I think it might be the best way of handling this?
-    let giver_balances = match UserBalances::from_user_and_guild_ids(giver.id, guild.id, db).await {
-        Ok(record) => record,
-        Err(sqlx::Error::RowNotFound) => {
-            ctx.send(poise::CreateReply::default().embed(embeds::user_not_in_db()))
+    let request = GiveRequest {
+        giver_id: giver.id,
+        receiver_id: receiver.id,
+        receiver_is_bot: receiver.bot,
+        guild_id: guild.id,
+        amount,
+    };
+    let preview = match request.check(economy, locale).await? {
+        Ok(preview) => preview,
+        Err(embed) => {
+            ctx.send(poise::CreateReply::default().embed(embed.author(guild_author)))
                 .await?;
             return Ok(());
         }
-        Err(err) => return Err(Box::new(err)),
     };
-    let receiver_balances =
-        match UserBalances::from_user_and_guild_ids(receiver.id, guild.id, db).await {
-            Ok(record) => record,
-            Err(sqlx::Error::RowNotFound) => {
-                ctx.send(poise::CreateReply::default().embed(embeds::user_not_in_db()))
-                    .await?;
-                return Ok(());
-            }
-            Err(err) => return Err(Box::new(err)),
-        };
-
-    if giver_balances.wallet_balance < amount {
-        let embed = CreateEmbed::new()
-            .title("Not enough money")
-            .description(format!(
-                "You need **{remaining_coins}** more coins to give **{amount}.**",
-                remaining_coins = amount - giver_balances.wallet_balance,
-                amount = amount
-            ))
-            .author(CreateEmbedAuthor::new(guild.name).icon_url(guild_icon_url.clone()))
-            .colour(Colour::RED);
 
-        ctx.send(poise::CreateReply::default().embed(embed)).await?;
-        return Ok(());
-    }
-
-    let guild_author = CreateEmbedAuthor::new(guild.name).icon_url(guild_icon_url);
     let reply = {
         let components = vec![CreateActionRow::Buttons(vec![
             CreateButton::new("confirm_give")
-                .label("Give")
+                .label(tr!(locale, "give-confirm"))
                 .style(ButtonStyle::Success),
             CreateButton::new("cancel_give")
-                .label("Cancel")
+                .label(tr!(locale, "cancel"))
                 .style(ButtonStyle::Secondary),
         ])];
 
         let embed = CreateEmbed::new()
-            .title(format!("Give to @{username}?", username = receiver.name))
-            .description(format!(
-                "Are you sure you want to give **{amount}** coins to **@{username}**?\n\nYour future balances are below.",
+            .title(tr!(locale, "give-confirm-title", username = receiver.name.as_str()))
+            .description(tr!(
+                locale,
+                "give-confirm-description",
                 amount = amount,
-                username = receiver.name
+                username = receiver.name.as_str()
             ))
-            .field("Your wallet balance", (giver_balances.wallet_balance - amount).to_string(), true)
-            .field(format!("@{username}'s wallet balance", username = receiver.name), (receiver_balances.wallet_balance + amount).to_string(), true)
+            .field(tr!(locale, "give-your-wallet"), preview.from.wallet.to_string(), true)
+            .field(
+                tr!(locale, "give-their-wallet", username = receiver.name.as_str()),
+                preview.to.wallet.to_string(),
+                true,
+            )
             .author(guild_author.clone())
             .colour(Colour::GOLD);
 
@@ -113,13 +90,25 @@ pub async fn give(
     let reply_handle = ctx.send(reply).await?;
     let m = reply_handle.message().await?;
 
-    let Some(interaction) = m
-        .await_component_interaction(&ctx.serenity_context().shard)
-        .timeout(Duration::from_secs(60 * 3))
-        .author_id(ctx.author().id)
-        .await
-    else {
-        m.reply(&ctx, "Timed out").await.unwrap();
+    let interaction = tokio::select! {
+        interaction = m
+            .await_component_interaction(&ctx.serenity_context().shard)
+            .timeout(Duration::from_secs(60 * 3))
+            .author_id(ctx.author().id) => interaction,
+        () = ctx.data().shutdown.triggered() => {
+            reply_handle
+                .edit(
+                    ctx,
+                    poise::CreateReply::default()
+                        .embed(embeds::bot_restarting(locale))
+                        .components(vec![]),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+    let Some(interaction) = interaction else {
+        m.reply(&ctx, tr!(locale, "timed-out")).await.unwrap();
         m.delete(&ctx).await?;
         return Ok(());
     };
@@ -135,36 +124,35 @@ pub async fn give(
 
     match user_selection {
         UserSelection::Confirm => {
-            let transaction = PerformGive {
-                giver_id: giver.id,
-                receiver_id: receiver.id,
-                guild_id: guild.id,
-                amount,
-                pool: db.clone(),
-            };
             ctx.defer().await?;
-            transaction.execute().await?;
-
-            let embed = CreateEmbed::new()
-                .title("Success!")
-                .description("The new balances are below.")
-                .field(
-                    "Your wallet balance",
-                    (giver_balances.wallet_balance - amount).to_string(),
-                    true,
-                )
-                .field(
-                    format!("@{username}'s wallet balance", username = receiver.name),
-                    (receiver_balances.wallet_balance + amount).to_string(),
-                    true,
-                )
-                .author(guild_author.clone())
-                .colour(Colour::DARK_TEAL); // FIXME: use a better color
+            let embed = match economy
+                .transfer(guild.id, giver.id, receiver.id, amount)
+                .await
+            {
+                Ok(transfer) => CreateEmbed::new()
+                    .title(tr!(locale, "success-title"))
+                    .description(tr!(locale, "give-success"))
+                    .field(
+                        tr!(locale, "give-your-wallet"),
+                        transfer.from.wallet.to_string(),
+                        true,
+                    )
+                    .field(
+                        tr!(locale, "give-their-wallet", username = receiver.name.as_str()),
+                        transfer.to.wallet.to_string(),
+                        true,
+                    )
+                    .colour(Colour::DARK_TEAL), // FIXME: use a better color
+                // Balances may have changed while waiting for confirmation
+                Err(err) => rejection_embed(err, locale)?,
+            };
 
             let mut msg = interaction.message.clone();
             msg.edit(
                 ctx,
-                serenity::EditMessage::new().embed(embed).components(vec![]),
+                serenity::EditMessage::new()
+                    .embed(embed.author(guild_author.clone()))
+                    .components(vec![]),
             )
             .await?;
 
@@ -177,7 +165,7 @@ pub async fn give(
             msg.edit(
                 ctx,
                 serenity::EditMessage::new()
-                    .content("Cancelled command.")
+                    .content(tr!(locale, "cancelled-command"))
                     .suppress_embeds(true)
                     .components(vec![]),
             )
@@ -192,42 +180,120 @@ pub async fn give(
     Ok(())
 }
 
-struct PerformGive {
+struct GiveRequest {
     giver_id: UserId,
     receiver_id: UserId,
+    receiver_is_bot: bool,
     guild_id: GuildId,
     amount: i32,
-    pool: sqlx::PgPool,
 }
-impl PerformGive {
-    pub async fn execute(&self) -> Result<(), Error> {
-        let mut transaction = self.pool.begin().await?;
-        let giver_id = self.giver_id.to_string();
-        let receiver_id = self.receiver_id.to_string();
-        let guild_id = &self.guild_id.to_string();
-
-        // Increase receiver's wallet balance
-        sqlx::query(
-            "UPDATE users SET wallet_balance = wallet_balance + $1 WHERE user_id = $2 AND guild_id = $3",
-        )
-        .bind(self.amount)
-        .bind(receiver_id)
-        .bind(guild_id)
-        .execute(&mut *transaction)
-        .await?;
 
-        // Decrease giver's wallet balance
-        sqlx::query(
-            "UPDATE users SET wallet_balance = wallet_balance - $1 WHERE user_id = $2 AND guild_id = $3",
-        )
-        .bind(self.amount)
-        .bind(giver_id)
-        .bind(guild_id)
-        .execute(&mut *transaction)
-        .await?;
+impl GiveRequest {
+    /// Checks whether the give can go ahead, returning both users' balances afterwards, or
+    /// an embed explaining why it can't.
+    async fn check(
+        &self,
+        economy: &EconomyService<impl Store>,
+        locale: Locale,
+    ) -> Result<Result<Transfer, CreateEmbed>, EconomyError> {
+        if self.receiver_is_bot {
+            return Ok(Err(embeds::bots_not_allowed(locale)));
+        }
+
+        match economy
+            .preview_transfer(self.guild_id, self.giver_id, self.receiver_id, self.amount)
+            .await
+        {
+            Ok(preview) => Ok(Ok(preview)),
+            Err(err) => rejection_embed(err, locale).map(Err),
+        }
+    }
+}
+
+/// Explains why a transfer was refused, passing through errors that aren't the user's fault.
+fn rejection_embed(err: EconomyError, locale: Locale) -> Result<CreateEmbed, EconomyError> {
+    match err {
+        EconomyError::NotRegistered(_) => Ok(embeds::user_not_in_db(locale)),
+        EconomyError::TransferToSelf => Ok(embeds::cannot_use_yourself(locale)),
+        EconomyError::InsufficientFunds { shortfall, amount } => Ok(CreateEmbed::new()
+            .title(tr!(locale, "give-not-enough-title"))
+            .description(tr!(
+                locale,
+                "give-not-enough-description",
+                shortfall = shortfall,
+                amount = amount
+            ))
+            .colour(Colour::RED)),
+        err => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::MemoryStore;
+    use crate::test_utils::{embed_title, GUILD, OTHER_USER, USER};
+
+    const EN: Locale = Locale::ENGLISH;
+
+    fn request(amount: i32) -> GiveRequest {
+        GiveRequest {
+            giver_id: USER,
+            receiver_id: OTHER_USER,
+            receiver_is_bot: false,
+            guild_id: GUILD,
+            amount,
+        }
+    }
+
+    fn economy_with(accounts: &[(UserId, i32, i32)]) -> EconomyService<MemoryStore> {
+        EconomyService::new(MemoryStore::with_accounts(GUILD, accounts))
+    }
+
+    #[tokio::test]
+    async fn rejects_bots() {
+        let request = GiveRequest {
+            receiver_is_bot: true,
+            ..request(10)
+        };
+        let result = request.check(&economy_with(&[]), EN).await.unwrap();
+        assert_eq!(result, Err(embeds::bots_not_allowed(EN)));
+    }
+
+    #[tokio::test]
+    async fn rejects_giving_to_yourself() {
+        let request = GiveRequest {
+            receiver_id: USER,
+            ..request(10)
+        };
+        let result = request.check(&economy_with(&[]), EN).await.unwrap();
+        assert_eq!(result, Err(embeds::cannot_use_yourself(EN)));
+    }
+
+    #[tokio::test]
+    async fn rejects_unregistered_receiver() {
+        let economy = economy_with(&[(USER, 50, 0)]);
+
+        let result = request(10).check(&economy, EN).await.unwrap();
+        assert_eq!(result, Err(embeds::user_not_in_db(EN)));
+    }
+
+    #[tokio::test]
+    async fn rejects_giving_more_than_the_wallet() {
+        let economy = economy_with(&[(USER, 5, 1000), (OTHER_USER, 0, 0)]);
+
+        let embed = request(10).check(&economy, EN).await.unwrap().unwrap_err();
+        assert_eq!(embed_title(&embed), "Not enough money");
+        let description = serde_json::to_value(&embed).unwrap()["description"].to_string();
+        assert!(description.contains("10"), "{description}");
+    }
 
-        transaction.commit().await?;
+    #[tokio::test]
+    async fn previews_the_new_wallets() {
+        let economy = economy_with(&[(USER, 50, 0), (OTHER_USER, 5, 0)]);
 
-        Ok(())
+        let preview = request(10).check(&economy, EN).await.unwrap().unwrap();
+        assert_eq!(preview.from.wallet, 40);
+        assert_eq!(preview.to.wallet, 15);
     }
 }
@@ -7,4 +7,22 @@ macro_rules! register_commands {
     };
 }
 
-register_commands!(user_info, about, avatar, balance, give, register, xkcd);
+register_commands!(
+    user_info,
+    server_info,
+    role_info,
+    channel_info,
+    about,
+    avatar,
+    banner,
+    balance,
+    ecoadmin,
+    give,
+    register,
+    privacy,
+    settings,
+    xkcd,
+    feed
+);
+pub use avatar::{avatar_menu, avatar_message_menu};
+pub use user_info::user_info_menu;
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+use serenity::{
+    ButtonStyle, Colour, CreateActionRow, CreateAttachment, CreateButton, CreateEmbed,
+    CreateMessage,
+};
+
+use crate::embeds;
+use crate::i18n::{self, tr, Locale};
+use crate::privacy::{self, Deletion};
+use crate::{Context, Error};
+
+#[poise::command(slash_command, subcommands("export", "delete"))]
+#[allow(clippy::unused_async)]
+pub async fn privacy(_: Context<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+/// DMs you a copy of everything Avion stores about you.
+#[poise::command(slash_command)]
+pub async fn export(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let locale = i18n::locale(ctx);
+
+    let data = privacy::export(&ctx.data().db, ctx.author().id).await?;
+    let file = CreateAttachment::bytes(serde_json::to_vec_pretty(&data)?, "avion-data.json");
+    let message = CreateMessage::new()
+        .content(tr!(locale, "privacy-export-message"))
+        .add_file(file);
+
+    let embed = match ctx.author().direct_message(ctx, message).await {
+        Ok(_) => CreateEmbed::new()
+            .title(tr!(locale, "privacy-export-sent-title"))
+            .description(tr!(locale, "privacy-export-sent-description"))
+            .colour(Colour::DARK_TEAL),
+        Err(_) => CreateEmbed::new()
+            .title(tr!(locale, "privacy-export-failed-title"))
+            .description(tr!(locale, "privacy-export-failed-description"))
+            .colour(Colour::RED),
+    };
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes everything Avion stores about you, in every server.
+#[poise::command(slash_command)]
+pub async fn delete(ctx: Context<'_>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let embed = CreateEmbed::new()
+        .title(tr!(locale, "privacy-delete-confirm-title"))
+        .description(tr!(locale, "privacy-delete-confirm-description"))
+        .colour(Colour::GOLD);
+    let components = vec![CreateActionRow::Buttons(vec![
+        CreateButton::new("confirm_delete")
+            .label(tr!(locale, "privacy-delete-confirm"))
+            .style(ButtonStyle::Danger),
+        CreateButton::new("cancel_delete")
+            .label(tr!(locale, "cancel"))
+            .style(ButtonStyle::Secondary),
+    ])];
+
+    let reply_handle = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(embed)
+                .components(components)
+                .ephemeral(true),
+        )
+        .await?;
+    let m = reply_handle.message().await?;
+
+    let interaction = tokio::select! {
+        interaction = m
+            .await_component_interaction(&ctx.serenity_context().shard)
+            .timeout(Duration::from_secs(60))
+            .author_id(ctx.author().id) => interaction,
+        () = ctx.data().shutdown.triggered() => None,
+    };
+
+    let embed = match interaction {
+        Some(interaction) if interaction.data.custom_id == "confirm_delete" => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            let deletion = privacy::delete(&ctx.data().db, ctx.author().id).await?;
+            deleted_embed(&deletion, locale)
+        }
+        Some(interaction) => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            CreateEmbed::new()
+                .title(tr!(locale, "cancelled-title"))
+                .description(tr!(locale, "privacy-delete-nothing-deleted"))
+                .colour(Colour::BLUE)
+        }
+        None if ctx.data().shutdown.is_triggered() => embeds::bot_restarting(locale),
+        None => CreateEmbed::new()
+            .title(tr!(locale, "timed-out"))
+            .description(tr!(locale, "privacy-delete-nothing-deleted"))
+            .colour(Colour::BLUE),
+    };
+    reply_handle
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(embed)
+                .components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn deleted_embed(deletion: &Deletion, locale: Locale) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .title(tr!(locale, "privacy-deleted-title"))
+        .description(tr!(locale, "privacy-deleted-description", rows = deletion.rows))
+        .colour(Colour::DARK_TEAL);
+    if deletion.held_guilds.is_empty() {
+        return embed;
+    }
+
+    let guilds = deletion
+        .held_guilds
+        .iter()
+        .map(|guild_id| format!("`{guild_id}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    embed.field(
+        tr!(locale, "privacy-deleted-held-title"),
+        tr!(locale, "privacy-deleted-held-description", guilds = guilds),
+        false,
+    )
+}
@@ -1,48 +1,68 @@
+use crate::economy::{EconomyError, EconomyService, Store};
+use crate::i18n::{self, tr, Locale};
 use crate::{Context, Error};
-use poise::serenity_prelude::{Colour, CreateEmbed};
+use poise::serenity_prelude::{Colour, CreateEmbed, GuildId, UserId};
 
 /// Register your user account in the server economy.
 #[poise::command(slash_command, guild_only)]
 pub async fn register(ctx: Context<'_>) -> Result<(), Error> {
-    let db = &ctx.data().db;
-
-    let user_exists = sqlx::query!(
-        "
-        SELECT EXISTS(SELECT 1 FROM users WHERE user_id = $1 AND guild_id = $2)
-        ",
-        ctx.author().id.to_string(),
-        ctx.guild_id().unwrap().to_string()
+    let embed = register_embed(
+        &ctx.data().economy,
+        i18n::locale(ctx),
+        ctx.author().id,
+        ctx.guild_id().unwrap(),
     )
-    .fetch_one(db)
-    .await?
-    .exists
-    .unwrap_or(false); // TODO: could probably change sql query to return a boolean
-
-    #[allow(clippy::if_not_else)]
-    if !user_exists {
-        sqlx::query!(
-            "
-            INSERT into users (user_id, guild_id)
-            VALUES ($1, $2)
-            ",
-            ctx.author().id.to_string(),
-            ctx.guild_id().unwrap().to_string()
-        )
-        .execute(db)
-        .await?;
-
-        let embed = CreateEmbed::new()
-            .title("Success!")
+    .await?;
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+async fn register_embed(
+    economy: &EconomyService<impl Store>,
+    locale: Locale,
+    user_id: UserId,
+    guild_id: GuildId,
+) -> Result<CreateEmbed, EconomyError> {
+    match economy.register(guild_id, user_id).await {
+        Ok(()) => Ok(CreateEmbed::new()
+            .title(tr!(locale, "success-title"))
             .colour(Colour::DARK_TEAL) // FIXME: use a better color
-            .description("Successfully registered your account in the server economy!");
-        ctx.send(poise::CreateReply::default().embed(embed)).await?;
-    } else {
-        let embed = CreateEmbed::new()
-            .title("Error")
+            .description(tr!(locale, "register-success"))),
+        Err(EconomyError::AlreadyRegistered) => Ok(CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
             .colour(Colour::RED)
-            .description("You are already registered in the server economy!");
-        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+            .description(tr!(locale, "register-already-registered"))),
+        Err(err) => Err(err),
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::{Balances, MemoryStore};
+    use crate::test_utils::{embed_title, GUILD, USER};
+
+    const EN: Locale = Locale::ENGLISH;
+
+    #[tokio::test]
+    async fn registers_with_empty_balances() {
+        let economy = EconomyService::new(MemoryStore::default());
+
+        let embed = register_embed(&economy, EN, USER, GUILD).await.unwrap();
+        assert_eq!(embed_title(&embed), "Success!");
+        assert_eq!(
+            economy.get_balances(GUILD, USER).await.unwrap(),
+            Balances::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn cannot_register_twice() {
+        let economy = EconomyService::new(MemoryStore::default());
+
+        let _ = register_embed(&economy, EN, USER, GUILD).await.unwrap();
+        let embed = register_embed(&economy, EN, USER, GUILD).await.unwrap();
+        assert_eq!(embed_title(&embed), "Error");
+    }
 }
@@ -0,0 +1,126 @@
+use super::user_info::permission_summary;
+use crate::i18n::{self, tr};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use serenity::{Colour, GuildId, RoleId};
+use tracing::warn;
+
+use crate::util::timestamp::{Format as TimestampFormat, TimestampExt};
+
+/// How many members to fetch at once when counting a role's members.
+const MEMBER_PAGE_SIZE: u64 = 1000;
+/// Counting members takes a request per page, so bigger servers don't get a count.
+const MAX_COUNTED_MEMBERS: u64 = 10_000;
+
+/// Displays a role's colour, position, members, permissions, and creation date
+#[poise::command(slash_command, guild_only, rename = "roleinfo")]
+pub async fn role_info(
+    ctx: Context<'_>,
+    #[description = "The role to show"] role: serenity::Role,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let guild_id = ctx.guild_id().unwrap();
+    if ctx.data().member_counts {
+        // Counting members can take a few requests
+        ctx.defer().await?;
+    }
+
+    let members = match member_count(ctx, guild_id, role.id).await {
+        Ok(Some(count)) => count.to_string(),
+        Ok(None) => tr!(locale, "userinfo-unknown"),
+        Err(err) => {
+            warn!(error = %err, %guild_id, "Failed to count role members");
+            tr!(locale, "userinfo-unknown")
+        }
+    };
+    let created_at = role.id.created_at();
+    let created = format!(
+        "{} ({})",
+        created_at.to_discord_timestamp(TimestampFormat::LongDate),
+        created_at.to_discord_timestamp(TimestampFormat::Relative)
+    );
+    let (colour, embed_colour) = if role.colour.0 == 0 {
+        (tr!(locale, "userinfo-none"), Colour::BLUE)
+    } else {
+        (format!("`#{}`", role.colour.hex()), role.colour)
+    };
+    let yes_no = |value: bool| tr!(locale, if value { "yes" } else { "no" });
+
+    let embed = serenity::CreateEmbed::new()
+        .title(role.name.clone())
+        .colour(embed_colour)
+        .fields(vec![
+            (tr!(locale, "roleinfo-colour"), colour, true),
+            (
+                tr!(locale, "roleinfo-position"),
+                role.position.to_string(),
+                true,
+            ),
+        ])
+        .field("", "", false)
+        .fields(vec![
+            (tr!(locale, "roleinfo-members"), members, true),
+            (tr!(locale, "roleinfo-created"), created, true),
+        ])
+        .field("", "", false)
+        .fields(vec![
+            (
+                tr!(locale, "roleinfo-mentionable"),
+                yes_no(role.mentionable),
+                true,
+            ),
+            (tr!(locale, "roleinfo-hoisted"), yes_no(role.hoist), true),
+        ])
+        .field(
+            tr!(locale, "userinfo-permissions"),
+            permission_summary(locale, role.permissions),
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            locale,
+            "roleinfo-id",
+            id = role.id.to_string()
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// How many members have a role, or `None` if the server is too big to count them or
+/// `features.member_counts` is off.
+async fn member_count(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    role_id: RoleId,
+) -> Result<Option<u64>, serenity::Error> {
+    // Listing members needs the privileged Server Members intent
+    if !ctx.data().member_counts {
+        return Ok(None);
+    }
+    let Some(total) = ctx.guild().map(|guild| guild.member_count) else {
+        return Ok(None);
+    };
+    // Everyone has the @everyone role, which shares the server's ID
+    if role_id.get() == guild_id.get() {
+        return Ok(Some(total));
+    }
+    if total > MAX_COUNTED_MEMBERS {
+        return Ok(None);
+    }
+
+    let mut count = 0;
+    let mut after = None;
+    loop {
+        let page = guild_id
+            .members(ctx, Some(MEMBER_PAGE_SIZE), after)
+            .await?;
+        count += page
+            .iter()
+            .filter(|member| member.roles.contains(&role_id))
+            .count() as u64;
+        if (page.len() as u64) < MEMBER_PAGE_SIZE {
+            return Ok(Some(count));
+        }
+        after = page.last().map(|member| member.user.id);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::i18n::{self, tr};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use serenity::Colour;
+
+use crate::util::timestamp::{Format as TimestampFormat, TimestampExt};
+
+/// Displays this server's owner, creation date, size, and boost level
+#[poise::command(slash_command, guild_only, rename = "serverinfo")]
+pub async fn server_info(ctx: Context<'_>) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+
+    // The cache can't be held across an await, so the embed is built before replying
+    let embed = ctx.guild().map(|guild| {
+        let created_at = guild.id.created_at();
+        let created = format!(
+            "{} ({})",
+            created_at.to_discord_timestamp(TimestampFormat::LongDate),
+            created_at.to_discord_timestamp(TimestampFormat::Relative)
+        );
+        let boosts = tr!(
+            locale,
+            "serverinfo-boost-level",
+            level = u8::from(guild.premium_tier),
+            boosts = guild.premium_subscription_count.unwrap_or_default()
+        );
+        let mut features: Vec<_> = guild
+            .features
+            .iter()
+            .map(|feature| feature_name(feature))
+            .collect();
+        features.sort_unstable();
+
+        let mut embed = serenity::CreateEmbed::new()
+            .title(guild.name.clone())
+            .colour(Colour::BLUE)
+            .fields(vec![
+                (
+                    tr!(locale, "serverinfo-owner"),
+                    format!("<@{}>", guild.owner_id),
+                    true,
+                ),
+                (tr!(locale, "serverinfo-created"), created, true),
+            ])
+            .field("", "", false)
+            .fields(vec![
+                (
+                    tr!(locale, "serverinfo-members"),
+                    guild.member_count.to_string(),
+                    true,
+                ),
+                (
+                    tr!(locale, "serverinfo-channels"),
+                    guild.channels.len().to_string(),
+                    true,
+                ),
+                (
+                    tr!(locale, "serverinfo-roles"),
+                    guild.roles.len().to_string(),
+                    true,
+                ),
+            ])
+            .field("", "", false)
+            .fields(vec![
+                (tr!(locale, "serverinfo-boosts"), boosts, true),
+                (
+                    tr!(locale, "serverinfo-verification"),
+                    tr!(
+                        locale,
+                        "serverinfo-verification-level",
+                        level = u8::from(guild.verification_level)
+                    ),
+                    true,
+                ),
+            ])
+            .field(
+                tr!(locale, "serverinfo-features"),
+                if features.is_empty() {
+                    tr!(locale, "serverinfo-no-features")
+                } else {
+                    features.join(", ")
+                },
+                false,
+            )
+            .footer(serenity::CreateEmbedFooter::new(tr!(
+                locale,
+                "serverinfo-id",
+                id = guild.id.to_string()
+            )));
+        if let Some(icon) = guild.icon_url() {
+            embed = embed.thumbnail(icon);
+        }
+        embed
+    });
+
+    // Only happens if Avion isn't in the server, like when it's installed by a user
+    let embed = embed.unwrap_or_else(|| {
+        serenity::CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(tr!(locale, "serverinfo-unavailable"))
+            .colour(Colour::RED)
+    });
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Turns a feature flag like `ANIMATED_ICON` into "Animated Icon".
+fn feature_name(feature: &str) -> String {
+    feature
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_lowercase();
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+
+    #[test]
+    fn names_features() {
+        assert_eq!(feature_name("ANIMATED_ICON"), "Animated Icon");
+        assert_eq!(feature_name("COMMUNITY"), "Community");
+        assert_eq!(feature_name("ROLE_ICONS_"), "Role Icons");
+    }
+
+    #[test]
+    fn describes_boost_levels() {
+        let level = |level: u8, boosts: u64| {
+            tr!(
+                Locale::ENGLISH,
+                "serverinfo-boost-level",
+                level = level,
+                boosts = boosts
+            )
+        };
+        assert_eq!(level(0, 1), "No level (1 boost)");
+        assert_eq!(level(2, 7), "Level 2 (7 boosts)");
+    }
+}
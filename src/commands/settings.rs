@@ -0,0 +1,339 @@
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+use serenity::{
+    ButtonStyle, ChannelType, Colour, ComponentInteractionDataKind, CreateActionRow,
+    CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind,
+};
+
+use crate::command_settings::{CommandSettings, SETTINGS_COMMAND};
+use crate::i18n::{self, tr, Locale};
+use crate::{Context, Error};
+
+/// How long the settings panel keeps responding after its last use.
+const PANEL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("commands", "language"),
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD"
+)]
+#[allow(clippy::unused_async)]
+pub async fn settings(_: Context<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+#[allow(clippy::unused_async)]
+async fn autocomplete_command(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    configurable_commands(ctx)
+        .filter(|name| name.starts_with(partial))
+        .collect()
+}
+
+fn configurable_commands(ctx: Context<'_>) -> impl Iterator<Item = String> + '_ {
+    ctx.framework()
+        .options()
+        .commands
+        .iter()
+        .filter(|command| command.name != SETTINGS_COMMAND)
+        .map(|command| command.name.clone())
+}
+
+/// Disable commands, or restrict them to some channels or roles.
+#[poise::command(slash_command, guild_only)]
+pub async fn commands(
+    ctx: Context<'_>,
+    #[description = "The command to change - leave empty to see every change"]
+    #[autocomplete = "autocomplete_command"]
+    command: Option<String>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let guild_id = ctx.guild_id().unwrap();
+    let db = &ctx.data().db;
+
+    let Some(command) = command else {
+        let embed = overview_embed(&ctx.data().command_settings.list(guild_id), locale);
+        ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        return Ok(());
+    };
+    if !configurable_commands(ctx).any(|name| name == command) {
+        let embed = CreateEmbed::new()
+            .title(tr!(locale, "settings-unknown-command-title"))
+            .description(tr!(
+                locale,
+                "settings-unknown-command-description",
+                command = command.as_str()
+            ))
+            .colour(Colour::RED);
+        ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        return Ok(());
+    }
+
+    let mut settings = ctx.data().command_settings.get(guild_id, &command);
+    let reply_handle = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(panel_embed(&command, &settings, locale))
+                .components(panel_components(&settings, locale))
+                .ephemeral(true),
+        )
+        .await?;
+    let m = reply_handle.message().await?;
+
+    loop {
+        let interaction = tokio::select! {
+            interaction = m
+                .await_component_interaction(&ctx.serenity_context().shard)
+                .timeout(PANEL_TIMEOUT)
+                .author_id(ctx.author().id) => interaction,
+            () = ctx.data().shutdown.triggered() => None,
+        };
+        let Some(interaction) = interaction else {
+            break;
+        };
+
+        match (&interaction.data.kind, interaction.data.custom_id.as_str()) {
+            (ComponentInteractionDataKind::Button, "toggle_command") => {
+                settings.enabled = !settings.enabled;
+            }
+            (ComponentInteractionDataKind::Button, "reset_command") => {
+                settings = CommandSettings::default();
+            }
+            (ComponentInteractionDataKind::ChannelSelect { values }, _) => {
+                settings.channels.clone_from(values);
+            }
+            (ComponentInteractionDataKind::RoleSelect { values }, _) => {
+                settings.roles.clone_from(values);
+            }
+            _ => {}
+        }
+        ctx.data()
+            .command_settings
+            .save(db, guild_id, &command, &settings)
+            .await?;
+
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(panel_embed(&command, &settings, locale))
+                        .components(panel_components(&settings, locale)),
+                ),
+            )
+            .await?;
+    }
+
+    reply_handle
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(panel_embed(&command, &settings, locale))
+                .components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn describe(settings: &CommandSettings, locale: Locale) -> (String, String, String) {
+    let list = |mentions: Vec<String>| {
+        if mentions.is_empty() {
+            tr!(locale, "settings-any")
+        } else {
+            mentions.join(", ")
+        }
+    };
+
+    (
+        if settings.enabled {
+            tr!(locale, "settings-enabled")
+        } else {
+            tr!(locale, "settings-disabled")
+        },
+        list(settings.channels.iter().map(|id| format!("<#{id}>")).collect()),
+        list(settings.roles.iter().map(|id| format!("<@&{id}>")).collect()),
+    )
+}
+
+fn panel_embed(command: &str, settings: &CommandSettings, locale: Locale) -> CreateEmbed {
+    let (status, channels, roles) = describe(settings, locale);
+    CreateEmbed::new()
+        .title(tr!(locale, "settings-panel-title", command = command))
+        .description(tr!(locale, "settings-panel-description"))
+        .field(tr!(locale, "settings-status"), status, true)
+        .field(tr!(locale, "settings-channels"), channels, true)
+        .field(tr!(locale, "settings-roles"), roles, true)
+        .colour(Colour::BLUE)
+}
+
+fn panel_components(settings: &CommandSettings, locale: Locale) -> Vec<CreateActionRow> {
+    let toggle = if settings.enabled {
+        CreateButton::new("toggle_command")
+            .label(tr!(locale, "settings-disable"))
+            .style(ButtonStyle::Danger)
+    } else {
+        CreateButton::new("toggle_command")
+            .label(tr!(locale, "settings-enable"))
+            .style(ButtonStyle::Success)
+    };
+
+    vec![
+        CreateActionRow::Buttons(vec![
+            toggle,
+            CreateButton::new("reset_command")
+                .label(tr!(locale, "settings-reset"))
+                .style(ButtonStyle::Secondary),
+        ]),
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "command_channels",
+                CreateSelectMenuKind::Channel {
+                    channel_types: Some(vec![ChannelType::Text, ChannelType::News]),
+                    default_channels: Some(settings.channels.clone()),
+                },
+            )
+            .placeholder(tr!(locale, "settings-channels-placeholder"))
+            .min_values(0)
+            .max_values(25),
+        ),
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "command_roles",
+                CreateSelectMenuKind::Role {
+                    default_roles: Some(settings.roles.clone()),
+                },
+            )
+            .placeholder(tr!(locale, "settings-roles-placeholder"))
+            .min_values(0)
+            .max_values(25),
+        ),
+    ]
+}
+
+fn overview_embed(settings: &[(String, CommandSettings)], locale: Locale) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .title(tr!(locale, "settings-overview-title"))
+        .colour(Colour::BLUE);
+    if settings.is_empty() {
+        return embed.description(tr!(locale, "settings-overview-empty"));
+    }
+
+    settings.iter().fold(embed, |embed, (command, settings)| {
+        let (status, channels, roles) = describe(settings, locale);
+        embed.field(
+            format!("/{command}"),
+            tr!(
+                locale,
+                "settings-overview-command",
+                status = status,
+                channels = channels,
+                roles = roles
+            ),
+            false,
+        )
+    })
+}
+
+#[allow(clippy::unused_async)]
+async fn autocomplete_language(
+    _: Context<'_>,
+    partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+    Locale::all()
+        .filter(|locale| {
+            locale.name().to_lowercase().contains(&partial)
+                || locale.code().to_lowercase().starts_with(&partial)
+        })
+        .map(|locale| serenity::AutocompleteChoice::new(locale.name(), locale.code()))
+        .collect()
+}
+
+/// Set the language Avion replies in, instead of each member's own.
+#[poise::command(slash_command, guild_only)]
+pub async fn language(
+    ctx: Context<'_>,
+    #[description = "The language to use - leave empty to use each member's own"]
+    #[autocomplete = "autocomplete_language"]
+    language: Option<String>,
+) -> Result<(), Error> {
+    let locale = match language.as_deref().map(Locale::from_code) {
+        Some(Some(locale)) => Some(locale),
+        Some(None) => {
+            let locale = i18n::locale(ctx);
+            let embed = CreateEmbed::new()
+                .title(tr!(locale, "settings-language-unknown-title"))
+                .description(tr!(locale, "settings-language-unknown-description"))
+                .colour(Colour::RED);
+            ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+        None => None,
+    };
+
+    ctx.data()
+        .guild_locales
+        .set(&ctx.data().db, ctx.guild_id().unwrap(), locale)
+        .await?;
+
+    // Reply in the language that's now in effect
+    let reply_locale = i18n::locale(ctx);
+    let description = if let Some(locale) = locale {
+        tr!(
+            reply_locale,
+            "settings-language-set",
+            language = locale.name()
+        )
+    } else {
+        tr!(reply_locale, "settings-language-cleared")
+    };
+    let embed = CreateEmbed::new()
+        .title(tr!(reply_locale, "success-title"))
+        .description(description)
+        .colour(Colour::DARK_TEAL);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::{ChannelId, RoleId};
+
+    #[test]
+    fn overview_lists_restrictions() {
+        let settings = vec![
+            (
+                "give".to_string(),
+                CommandSettings {
+                    channels: vec![ChannelId::new(100)],
+                    ..CommandSettings::default()
+                },
+            ),
+            (
+                "xkcd".to_string(),
+                CommandSettings {
+                    enabled: false,
+                    roles: vec![RoleId::new(200)],
+                    ..CommandSettings::default()
+                },
+            ),
+        ];
+
+        let expected = CreateEmbed::new()
+            .title("Command settings")
+            .colour(Colour::BLUE)
+            .field("/give", "Enabled\nChannels: <#100>\nRoles: Any", false)
+            .field("/xkcd", "Disabled\nChannels: Any\nRoles: <@&200>", false);
+        assert_eq!(overview_embed(&settings, Locale::ENGLISH), expected);
+    }
+}
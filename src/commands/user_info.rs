@@ -1,10 +1,46 @@
-// TODO: make this a user command too
+use crate::economy::EconomyError;
+use crate::i18n::{self, tr, Locale};
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
-use serenity::Colour;
+use serenity::{Colour, GuildId, Member, Permissions, UserId};
 
 use crate::util::timestamp::{Format as TimestampFormat, TimestampExt};
 
+/// The most roles to list before summing up the rest.
+const MAX_ROLES: usize = 20;
+
+/// Permissions worth pointing out, in the order they're listed.
+const NOTABLE_PERMISSIONS: [(Permissions, &str); 10] = [
+    (Permissions::MANAGE_GUILD, "userinfo-permission-manage-server"),
+    (Permissions::MANAGE_ROLES, "userinfo-permission-manage-roles"),
+    (
+        Permissions::MANAGE_CHANNELS,
+        "userinfo-permission-manage-channels",
+    ),
+    (
+        Permissions::MANAGE_MESSAGES,
+        "userinfo-permission-manage-messages",
+    ),
+    (
+        Permissions::MANAGE_NICKNAMES,
+        "userinfo-permission-manage-nicknames",
+    ),
+    (
+        Permissions::MANAGE_WEBHOOKS,
+        "userinfo-permission-manage-webhooks",
+    ),
+    (Permissions::KICK_MEMBERS, "userinfo-permission-kick-members"),
+    (Permissions::BAN_MEMBERS, "userinfo-permission-ban-members"),
+    (
+        Permissions::MODERATE_MEMBERS,
+        "userinfo-permission-timeout-members",
+    ),
+    (
+        Permissions::MENTION_EVERYONE,
+        "userinfo-permission-mention-everyone",
+    ),
+];
+
 /// Displays your or another user's username, avatar, and account creation date
 #[poise::command(slash_command, rename = "userinfo")]
 pub async fn user_info(
@@ -12,26 +48,207 @@ pub async fn user_info(
     #[description = "Selected user - defaults to you"] user: Option<serenity::User>,
 ) -> Result<(), Error> {
     let u = user.as_ref().unwrap_or_else(|| ctx.author());
+    send_user_info(ctx, u).await
+}
+
+/// Displays a user's username, avatar, and account creation date
+#[poise::command(context_menu_command = "User Info", rename = "userinfo-menu")]
+pub async fn user_info_menu(ctx: Context<'_>, user: serenity::User) -> Result<(), Error> {
+    send_user_info(ctx, &user).await
+}
+
+async fn send_user_info(ctx: Context<'_>, u: &serenity::User) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
     let account_creation_date = u
         .created_at()
         .to_discord_timestamp(TimestampFormat::LongDate);
     let formatted_username = format!("@{username}", username = u.name);
     let display_name = u.global_name.clone().unwrap_or_else(|| u.name.clone());
 
-    let embed = serenity::CreateEmbed::new()
+    let mut embed = serenity::CreateEmbed::new()
         .title(formatted_username)
         .thumbnail(u.face())
         .colour(Colour::BLUE)
         .fields(vec![
-            ("Display name", display_name, true),
-            ("Account creation date", account_creation_date, true),
+            (tr!(locale, "userinfo-display-name"), display_name, true),
+            (
+                tr!(locale, "userinfo-created"),
+                account_creation_date,
+                true,
+            ),
         ])
         .field("", "", false)
         .fields(vec![
-            ("User ID", format!("`{}`", u.id), true),
-            ("Is bot", if u.bot { "Yes" } else { "No" }.into(), true),
+            (tr!(locale, "userinfo-id"), format!("`{}`", u.id), true),
+            (
+                tr!(locale, "userinfo-bot"),
+                tr!(locale, if u.bot { "yes" } else { "no" }),
+                true,
+            ),
         ]);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Some(member) = fetch_member(ctx, guild_id, u.id).await? {
+            embed = member_fields(ctx, locale, embed, &member);
+        }
+
+        match ctx.data().economy.get_balances(guild_id, u.id).await {
+            Ok(balances) => {
+                embed = embed.field(
+                    tr!(locale, "userinfo-balance"),
+                    tr!(
+                        locale,
+                        "userinfo-balance-value",
+                        wallet = balances.wallet,
+                        bank = balances.bank
+                    ),
+                    false,
+                );
+            }
+            Err(EconomyError::NotRegistered(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
     ctx.send(poise::CreateReply::default().embed(embed)).await?;
 
     Ok(())
 }
+
+/// Gets a user's membership of a guild, or `None` if they aren't in it, since users outside
+/// the guild can be picked too.
+pub async fn fetch_member(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<Member>, Error> {
+    match guild_id.member(ctx, user_id).await {
+        Ok(member) => Ok(Some(member)),
+        Err(serenity::Error::Http(err))
+            if err
+                .status_code()
+                .is_some_and(|status| status.as_u16() == 404) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Adds a member's nickname, join date, boost status, roles and permissions, coloured like
+/// their highest role.
+fn member_fields(
+    ctx: Context<'_>,
+    locale: Locale,
+    mut embed: serenity::CreateEmbed,
+    member: &Member,
+) -> serenity::CreateEmbed {
+    let joined = member.joined_at.map_or_else(
+        || tr!(locale, "userinfo-unknown"),
+        |joined| joined.to_discord_timestamp(TimestampFormat::LongDate),
+    );
+    let boosting = member.premium_since.map_or_else(
+        || tr!(locale, "no"),
+        |since| {
+            tr!(
+                locale,
+                "userinfo-boosting-since",
+                since = since.to_discord_timestamp(TimestampFormat::LongDate)
+            )
+        },
+    );
+
+    embed = embed
+        .field("", "", false)
+        .fields(vec![
+            (
+                tr!(locale, "userinfo-nickname"),
+                member
+                    .nick
+                    .clone()
+                    .unwrap_or_else(|| tr!(locale, "userinfo-none")),
+                true,
+            ),
+            (tr!(locale, "userinfo-joined"), joined, true),
+            (tr!(locale, "userinfo-boosting"), boosting, true),
+        ]);
+
+    // The cache can't be held across an await, so everything from it is read at once
+    let Some(guild) = ctx.guild() else {
+        return embed;
+    };
+    let mut roles: Vec<_> = member
+        .roles
+        .iter()
+        .filter_map(|role_id| guild.roles.get(role_id))
+        .collect();
+    roles.sort_by(|a, b| b.cmp(a));
+    let mut role_list = roles
+        .iter()
+        .take(MAX_ROLES)
+        .map(|role| format!("<@&{}>", role.id))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if roles.len() > MAX_ROLES {
+        role_list.push(' ');
+        role_list.push_str(&tr!(
+            locale,
+            "userinfo-more-roles",
+            count = roles.len() - MAX_ROLES
+        ));
+    }
+    if roles.is_empty() {
+        role_list = tr!(locale, "userinfo-none");
+    }
+    let permissions = permission_summary(locale, guild.member_permissions(member));
+    if let Some(colour) = roles.iter().map(|role| role.colour).find(|c| c.0 != 0) {
+        embed = embed.colour(colour);
+    }
+
+    embed
+        .field(
+            tr!(locale, "userinfo-roles", count = roles.len()),
+            role_list,
+            false,
+        )
+        .field(tr!(locale, "userinfo-permissions"), permissions, false)
+}
+
+/// Lists the notable permissions in `permissions`, or just says they're an administrator.
+pub fn permission_summary(locale: Locale, permissions: Permissions) -> String {
+    if permissions.administrator() {
+        return tr!(locale, "userinfo-permission-administrator");
+    }
+    let names: Vec<_> = NOTABLE_PERMISSIONS
+        .iter()
+        .filter(|(permission, _)| permissions.contains(*permission))
+        .map(|(_, key)| tr!(locale, key))
+        .collect();
+    if names.is_empty() {
+        tr!(locale, "userinfo-none")
+    } else {
+        names.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: Locale = Locale::ENGLISH;
+
+    #[test]
+    fn summarises_permissions() {
+        assert_eq!(
+            permission_summary(EN, Permissions::ADMINISTRATOR | Permissions::BAN_MEMBERS),
+            "Administrator"
+        );
+        assert_eq!(
+            permission_summary(
+                EN,
+                Permissions::SEND_MESSAGES | Permissions::BAN_MEMBERS | Permissions::MANAGE_GUILD
+            ),
+            "Manage Server, Ban Members"
+        );
+        assert_eq!(permission_summary(EN, Permissions::SEND_MESSAGES), "None");
+    }
+}
@@ -1,11 +1,27 @@
-use crate::{Context, Error};
+use std::fmt::Write;
+use std::time::Duration;
 
-use poise::serenity_prelude::{Colour, CreateEmbed};
+use crate::comics::{navigation, subscriptions, ComicProvider, Source};
+use crate::i18n::{self, tr};
+use crate::util::text::truncate;
+use crate::xkcd::search::{self, SearchResult};
+use crate::{embeds, Context, Error};
 
-mod api;
-use api::Xkcd;
+use poise::serenity_prelude::{
+    AutocompleteChoice, Colour, ComponentInteractionDataKind, CreateActionRow, CreateEmbed,
+    CreateInteractionResponse, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+    GuildChannel,
+};
 
-#[poise::command(slash_command, subcommands("today", "comic", "random"))]
+/// How many search results to offer.
+const SEARCH_RESULTS: i64 = 10;
+/// Discord's limit on select menu option labels.
+const MAX_LABEL_LENGTH: usize = 100;
+
+#[poise::command(
+    slash_command,
+    subcommands("today", "comic", "random", "search", "subscribe", "unsubscribe")
+)]
 #[allow(clippy::unused_async)]
 pub async fn xkcd(_: Context<'_>) -> Result<(), Error> {
     unreachable!()
@@ -14,44 +30,207 @@ pub async fn xkcd(_: Context<'_>) -> Result<(), Error> {
 /// Get today's comic from XKCD.
 #[poise::command(slash_command)]
 pub async fn today(ctx: Context<'_>) -> Result<(), Error> {
-    let xkcd = Xkcd::from_num(None, &ctx.data().client).await?;
-    ctx.send(poise::CreateReply::default().embed(xkcd.into_embed()))
-        .await?;
+    let xkcd = &*ctx.data().xkcd;
+    navigation::send(ctx, xkcd, xkcd.latest()).await
+}
 
-    Ok(())
+async fn autocomplete_comic(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let results = search::by_title(&ctx.data().db, partial, 25)
+        .await
+        .unwrap_or_default();
+    results
+        .into_iter()
+        .map(|result| AutocompleteChoice::new(choice_label(&result), result.num))
+        .collect()
 }
 
 /// Get a specific comic from XKCD.
 #[poise::command(slash_command)]
 pub async fn comic(
     ctx: Context<'_>,
-    #[description = "Comic number"] num: usize,
+    #[description = "Comic number"]
+    #[autocomplete = "autocomplete_comic"]
+    num: usize,
 ) -> Result<(), Error> {
-    if let Ok(xkcd) = Xkcd::from_num(Some(num), &ctx.data().client).await {
-        ctx.send(poise::CreateReply::default().embed(xkcd.into_embed()))
-            .await?;
-    } else {
-        let embed = CreateEmbed::default()
-            .title("Comic not found")
-            .description("It looks like the comic you requested does not exist. Sorry!")
-            .colour(Colour::RED);
+    let xkcd = &*ctx.data().xkcd;
+    navigation::send(ctx, xkcd, xkcd.get(num)).await
+}
+
+/// Get a random comic from XKCD.
+#[poise::command(slash_command)]
+pub async fn random(ctx: Context<'_>) -> Result<(), Error> {
+    let xkcd = &*ctx.data().xkcd;
+    navigation::send(ctx, xkcd, xkcd.random()).await
+}
 
+/// Search XKCD comics by title, alt text and transcript.
+#[poise::command(slash_command)]
+pub async fn search(
+    ctx: Context<'_>,
+    #[description = "What the comic is about"] query: String,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let results = search::search(&ctx.data().db, &query, SEARCH_RESULTS).await?;
+    if results.is_empty() {
+        let embed = CreateEmbed::new()
+            .title(tr!(locale, "xkcd-search-empty-title"))
+            .description(tr!(locale, "xkcd-search-empty-description"))
+            .colour(Colour::RED);
         ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for result in &results {
+        let _ = writeln!(description, "**#{}** {}", result.num, result.title);
+    }
+    let embed = CreateEmbed::new()
+        .title(tr!(locale, "xkcd-search-title", query = query.as_str()))
+        .description(description)
+        .colour(Colour::BLUE);
+    let options = results
+        .iter()
+        .map(|result| CreateSelectMenuOption::new(choice_label(result), result.num.to_string()))
+        .collect();
+    let menu = CreateSelectMenu::new("xkcd_search", CreateSelectMenuKind::String { options })
+        .placeholder(tr!(locale, "xkcd-search-placeholder"));
+
+    let reply_handle = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(embed.clone())
+                .components(vec![CreateActionRow::SelectMenu(menu)]),
+        )
+        .await?;
+    let m = reply_handle.message().await?;
+
+    let interaction = tokio::select! {
+        interaction = m
+            .await_component_interaction(&ctx.serenity_context().shard)
+            .timeout(Duration::from_secs(60 * 3))
+            .author_id(ctx.author().id) => interaction,
+        () = ctx.data().shutdown.triggered() => None,
+    };
+    let selected = interaction.as_ref().and_then(|interaction| {
+        let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind else {
+            return None;
+        };
+        values.first()?.parse::<usize>().ok()
+    });
+    if let Some(interaction) = &interaction {
+        interaction
+            .create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await?;
+    }
+
+    let reply = match selected {
+        Some(num) => {
+            let xkcd = &*ctx.data().xkcd;
+            navigation::reply(xkcd, xkcd.get(num).await, locale).await?
+        }
+        None if ctx.data().shutdown.is_triggered() => poise::CreateReply::default()
+            .embed(embeds::bot_restarting(locale))
+            .components(vec![]),
+        None => poise::CreateReply::default()
+            .embed(embed)
+            .components(vec![]),
     };
+    reply_handle.edit(ctx, reply).await?;
 
     Ok(())
 }
 
-/// Get a random comic from XKCD.
-#[poise::command(slash_command)]
-pub async fn random(ctx: Context<'_>) -> Result<(), Error> {
-    let data = ctx.data();
-    let latest = Xkcd::from_num(None, &data.client).await?;
-    let num = fastrand::usize(1..=latest.num);
-    let xkcd = Xkcd::from_num(Some(num), &data.client).await?;
+/// Post new XKCD comics in a channel as they come out.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn subscribe(
+    ctx: Context<'_>,
+    #[description = "The channel to post in - defaults to this one"]
+    #[channel_types("Text", "News")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let channel_id = channel.map_or_else(|| ctx.channel_id(), |channel| channel.id);
+    let xkcd = &*ctx.data().xkcd;
+    let latest = match xkcd.latest().await {
+        Ok(comic) => comic.num,
+        Err(err) => {
+            ctx.send(poise::CreateReply::default().embed(xkcd.error_embed(err, locale)))
+                .await?;
+            return Ok(());
+        }
+    };
 
-    ctx.send(poise::CreateReply::default().embed(xkcd.into_embed()))
-        .await?;
+    let subscribed = subscriptions::subscribe(
+        &ctx.data().db,
+        ctx.guild_id().unwrap(),
+        channel_id,
+        Source::Xkcd,
+        latest,
+    )
+    .await?;
+    let embed = if subscribed {
+        CreateEmbed::new()
+            .title(tr!(locale, "success-title"))
+            .description(tr!(
+                locale,
+                "xkcd-subscribed",
+                channel = format!("<#{channel_id}>")
+            ))
+            .colour(Colour::DARK_TEAL)
+    } else {
+        CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(tr!(
+                locale,
+                "xkcd-already-subscribed",
+                channel = format!("<#{channel_id}>")
+            ))
+            .colour(Colour::RED)
+    };
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
 
     Ok(())
 }
+
+/// Stop posting new XKCD comics in a channel.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn unsubscribe(
+    ctx: Context<'_>,
+    #[description = "The channel to stop posting in - defaults to this one"]
+    #[channel_types("Text", "News")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let locale = i18n::locale(ctx);
+    let channel_id = channel.map_or_else(|| ctx.channel_id(), |channel| channel.id);
+
+    let embed = if subscriptions::unsubscribe(&ctx.data().db, channel_id, Source::Xkcd).await? {
+        CreateEmbed::new()
+            .title(tr!(locale, "success-title"))
+            .description(tr!(
+                locale,
+                "xkcd-unsubscribed",
+                channel = format!("<#{channel_id}>")
+            ))
+            .colour(Colour::DARK_TEAL)
+    } else {
+        CreateEmbed::new()
+            .title(tr!(locale, "error-title"))
+            .description(tr!(
+                locale,
+                "xkcd-not-subscribed",
+                channel = format!("<#{channel_id}>")
+            ))
+            .colour(Colour::RED)
+    };
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Labels a comic like "#927: Standards", shortened to fit in a select menu.
+fn choice_label(result: &SearchResult) -> String {
+    truncate(
+        &format!("#{}: {}", result.num, result.title),
+        MAX_LABEL_LENGTH,
+    )
+}
@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use poise::serenity_prelude::GuildId;
+use reqwest::Url;
+use sentry::types::Dsn;
+use toml::{Table, Value};
+
+use crate::cooldowns::CooldownRule;
+
+/// The longest `cleanup.grace_period_days` can be, which keeps it from overflowing when
+/// turned into seconds.
+const MAX_GRACE_PERIOD_DAYS: u64 = 36_500;
+/// The most times `xkcd.retries` can retry a request. Each retry waits twice as long as the
+/// last, so many more would wait for days, and eventually overflow.
+const MAX_XKCD_RETRIES: u32 = 10;
+
+/// Every key Avion understands, alongside the environment variable that overrides it.
+///
+/// Keys are dotted paths into the TOML file, so `database.url` is `url` under `[database]`.
+const KEYS: &[(&str, &str)] = &[
+    ("discord_token", "DISCORD_TOKEN"),
+    ("discord_testing_guild_id", "DISCORD_TESTING_GUILD_ID"),
+    ("sentry_dsn", "SENTRY_DSN"),
+    ("database.url", "DATABASE_URL"),
+    ("database.max_connections", "DATABASE_MAX_CONNECTIONS"),
+    ("database.min_connections", "DATABASE_MIN_CONNECTIONS"),
+    (
+        "database.acquire_timeout_secs",
+        "DATABASE_ACQUIRE_TIMEOUT_SECS",
+    ),
+    ("database.idle_timeout_secs", "DATABASE_IDLE_TIMEOUT_SECS"),
+    (
+        "database.statement_cache_capacity",
+        "DATABASE_STATEMENT_CACHE_CAPACITY",
+    ),
+    ("database.connect_attempts", "DATABASE_CONNECT_ATTEMPTS"),
+    ("gateway.total_shards", "AVION_GATEWAY_TOTAL_SHARDS"),
+    ("gateway.shard_ids", "AVION_GATEWAY_SHARD_IDS"),
+    ("http.address", "AVION_HTTP_ADDRESS"),
+    ("features.xkcd", "AVION_FEATURES_XKCD"),
+    ("features.economy", "AVION_FEATURES_ECONOMY"),
+    ("features.feeds", "AVION_FEATURES_FEEDS"),
+    ("features.member_counts", "AVION_FEATURES_MEMBER_COUNTS"),
+    ("xkcd.base_url", "AVION_XKCD_BASE_URL"),
+    ("xkcd.cache_size", "AVION_XKCD_CACHE_SIZE"),
+    ("xkcd.latest_ttl_secs", "AVION_XKCD_LATEST_TTL_SECS"),
+    ("xkcd.persist_cache", "AVION_XKCD_PERSIST_CACHE"),
+    ("xkcd.timeout_secs", "AVION_XKCD_TIMEOUT_SECS"),
+    ("xkcd.retries", "AVION_XKCD_RETRIES"),
+    (
+        "cleanup.grace_period_days",
+        "AVION_CLEANUP_GRACE_PERIOD_DAYS",
+    ),
+];
+
+pub struct Config {
+    pub discord_token: String,
+    pub testing_guild_id: Option<GuildId>,
+    pub sentry_dsn: Option<Dsn>,
+    pub database: DatabaseConfig,
+    pub sharding: Sharding,
+    pub http: HttpConfig,
+    pub features: FeaturesConfig,
+    pub xkcd: XkcdConfig,
+    pub cleanup: CleanupConfig,
+    /// Cooldowns by qualified command name, overriding the defaults.
+    pub cooldowns: HashMap<String, CooldownRule>,
+}
+
+pub struct DatabaseConfig {
+    pub url: Url,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    /// How long a connection may sit idle before it is closed. `None` keeps it open forever.
+    pub idle_timeout: Option<Duration>,
+    pub statement_cache_capacity: usize,
+    /// How many times to try connecting at startup before giving up.
+    pub connect_attempts: u32,
+}
+
+/// Which gateway shards this process runs.
+pub enum Sharding {
+    /// Run as many shards as Discord recommends.
+    Auto,
+    /// Run `shards` out of `total`, so the bot can be split across processes.
+    Range { shards: Range<u32>, total: u32 },
+}
+
+/// An inclusive range of shard IDs, written as `3` or `0-3`.
+struct ShardIds(Range<u32>);
+
+impl FromStr for ShardIds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, last) = s.split_once('-').unwrap_or((s, s));
+        let first: u32 = first.trim().parse().map_err(|err| format!("{err}"))?;
+        let last: u32 = last.trim().parse().map_err(|err| format!("{err}"))?;
+        if first > last {
+            return Err(format!("{first} is greater than {last}"));
+        }
+        Ok(Self(first..last + 1))
+    }
+}
+
+pub struct HttpConfig {
+    /// Where to serve health checks. The server is disabled when unset.
+    pub address: Option<SocketAddr>,
+}
+
+/// Toggles for optional command groups.
+#[allow(clippy::struct_excessive_bools)] // Each is an independent toggle
+#[derive(Clone)]
+pub struct FeaturesConfig {
+    pub xkcd: bool,
+    pub economy: bool,
+    /// RSS and Atom feeds added by guild admins.
+    pub feeds: bool,
+    /// Counting a role's members in `/roleinfo`, which needs the privileged Server Members
+    /// intent.
+    pub member_counts: bool,
+}
+
+pub struct XkcdConfig {
+    /// Where to fetch comics from, so tests can use a local server instead of xkcd.com.
+    pub base_url: Url,
+    /// How many comics to keep in memory.
+    pub cache_size: NonZeroUsize,
+    /// How long to wait before checking for a new comic again.
+    pub latest_ttl: Duration,
+    /// Whether to serve comics from the database when they aren't in memory, so the cache
+    /// survives restarts. Comics are stored either way, for `/xkcd search`.
+    pub persist_cache: bool,
+    /// How long to wait for xkcd.com to respond.
+    pub timeout: Duration,
+    /// How many times to retry requests that time out or fail on xkcd.com's end.
+    pub retries: u32,
+}
+
+pub struct CleanupConfig {
+    /// How long to keep a guild's data after Avion is removed from it.
+    pub grace_period: Duration,
+}
+
+impl Config {
+    /// Loads the config file given by `--config` or `AVION_CONFIG` (if any), applies
+    /// environment variable overrides on top, and validates the result.
+    pub fn load() -> Result<Self> {
+        let mut table = match config_path()? {
+            Some(path) => {
+                let contents = std::fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?;
+                contents
+                    .parse::<Table>()
+                    .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))?
+            }
+            None => Table::new(),
+        };
+
+        for (key, var) in KEYS {
+            if let Ok(value) = std::env::var(var) {
+                insert(&mut table, key, Value::String(value));
+            }
+        }
+
+        Ok(Self::from_table(table)?)
+    }
+
+    fn from_table(table: Table) -> Result<Self, InvalidConfig> {
+        let mut v = Validator {
+            table,
+            errors: Vec::new(),
+        };
+
+        let discord_token = v.required::<String>("discord_token");
+        let testing_guild_id = v.optional::<GuildId>("discord_testing_guild_id");
+        let sentry_dsn = v.optional::<Dsn>("sentry_dsn");
+        let database_url = v.required::<Url>("database.url");
+        let max_connections = v.optional::<u32>("database.max_connections").unwrap_or(5);
+        let min_connections = v.optional::<u32>("database.min_connections").unwrap_or(0);
+        let acquire_timeout = v
+            .optional::<u64>("database.acquire_timeout_secs")
+            .unwrap_or(30);
+        let idle_timeout = v
+            .optional::<u64>("database.idle_timeout_secs")
+            .unwrap_or(600);
+        let statement_cache_capacity = v
+            .optional::<usize>("database.statement_cache_capacity")
+            .unwrap_or(100);
+        let connect_attempts = v.optional::<u32>("database.connect_attempts").unwrap_or(5);
+        let http_address = v.optional::<SocketAddr>("http.address");
+        let xkcd = v.optional::<bool>("features.xkcd").unwrap_or(true);
+        let economy = v.optional::<bool>("features.economy").unwrap_or(true);
+        let feeds = v.optional::<bool>("features.feeds").unwrap_or(true);
+        let member_counts = v
+            .optional::<bool>("features.member_counts")
+            .unwrap_or(false);
+        let grace_period_days = v.optional::<u64>("cleanup.grace_period_days").unwrap_or(30);
+        let xkcd_config = v.xkcd();
+        let cooldowns = v.cooldowns();
+
+        if max_connections == 0 {
+            v.invalid("database.max_connections", "must be at least 1");
+        }
+        if min_connections > max_connections {
+            v.invalid(
+                "database.min_connections",
+                "must not be greater than `database.max_connections`",
+            );
+        }
+        if acquire_timeout == 0 {
+            v.invalid("database.acquire_timeout_secs", "must be at least 1");
+        }
+        if connect_attempts == 0 {
+            v.invalid("database.connect_attempts", "must be at least 1");
+        }
+        if grace_period_days > MAX_GRACE_PERIOD_DAYS {
+            v.invalid(
+                "cleanup.grace_period_days",
+                format!("must be at most {MAX_GRACE_PERIOD_DAYS}"),
+            );
+        }
+        let sharding = v.sharding();
+        if let Some(url) = &database_url {
+            if !matches!(url.scheme(), "postgres" | "postgresql") {
+                v.invalid("database.url", "must be a `postgres://` URL");
+            }
+        }
+        v.reject_unknown_keys();
+
+        match (discord_token, database_url) {
+            (Some(discord_token), Some(url)) if v.errors.is_empty() => Ok(Self {
+                discord_token,
+                testing_guild_id,
+                sentry_dsn,
+                database: DatabaseConfig {
+                    url,
+                    max_connections,
+                    min_connections,
+                    acquire_timeout: Duration::from_secs(acquire_timeout),
+                    idle_timeout: (idle_timeout > 0).then(|| Duration::from_secs(idle_timeout)),
+                    statement_cache_capacity,
+                    connect_attempts,
+                },
+                sharding,
+                http: HttpConfig {
+                    address: http_address,
+                },
+                features: FeaturesConfig {
+                    xkcd,
+                    economy,
+                    feeds,
+                    member_counts,
+                },
+                xkcd: xkcd_config,
+                cleanup: CleanupConfig {
+                    grace_period: Duration::from_secs(grace_period_days * 24 * 60 * 60),
+                },
+                cooldowns,
+            }),
+            _ => Err(InvalidConfig(v.errors)),
+        }
+    }
+}
+
+/// Returns the config file path from `--config <path>`, falling back to `AVION_CONFIG`.
+fn config_path() -> Result<Option<PathBuf>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            let path = args
+                .next()
+                .ok_or_else(|| eyre!("`--config` requires a path"))?;
+            return Ok(Some(path.into()));
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Ok(Some(path.into()));
+        }
+    }
+
+    Ok(std::env::var_os("AVION_CONFIG").map(PathBuf::from))
+}
+
+/// Inserts `value` at the dotted `key`, creating intermediate tables as needed.
+fn insert(table: &mut Table, key: &str, value: Value) {
+    let mut table = table;
+    let mut segments = key.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none() {
+            table.insert(segment.to_string(), value);
+            return;
+        }
+        let entry = table
+            .entry(segment)
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        let Value::Table(inner) = entry else {
+            unreachable!()
+        };
+        table = inner;
+    }
+}
+
+/// Pulls typed values out of the merged table, collecting every problem instead of
+/// stopping at the first one.
+struct Validator {
+    table: Table,
+    errors: Vec<(String, String)>,
+}
+
+impl Validator {
+    fn required<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        let errors = self.errors.len();
+        let value = self.optional(key);
+        if value.is_none() && self.errors.len() == errors {
+            self.invalid(key, "is required but was not set");
+        }
+        value
+    }
+
+    fn optional<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        let raw = match self.take(key)? {
+            Value::String(s) => s,
+            Value::Integer(i) => i.to_string(),
+            Value::Boolean(b) => b.to_string(),
+            other => {
+                self.invalid(
+                    key,
+                    format!("unsupported value type `{}`", other.type_str()),
+                );
+                return None;
+            }
+        };
+
+        match raw.parse() {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.invalid(key, format!("invalid value `{raw}`: {err}"));
+                None
+            }
+        }
+    }
+
+    /// Reads `gateway.total_shards` and `gateway.shard_ids`.
+    fn sharding(&mut self) -> Sharding {
+        let total_shards = self.optional::<u32>("gateway.total_shards");
+        let shard_ids = self.optional::<ShardIds>("gateway.shard_ids");
+        match (shard_ids, total_shards) {
+            (None, None) => Sharding::Auto,
+            (_, Some(0)) => {
+                self.invalid("gateway.total_shards", "must be at least 1");
+                Sharding::Auto
+            }
+            (None, Some(total)) => Sharding::Range {
+                shards: 0..total,
+                total,
+            },
+            (Some(ShardIds(shards)), Some(total)) => {
+                if shards.end > total {
+                    self.invalid(
+                        "gateway.shard_ids",
+                        "must be lower than `gateway.total_shards`",
+                    );
+                }
+                Sharding::Range { shards, total }
+            }
+            (Some(_), None) => {
+                self.invalid(
+                    "gateway.shard_ids",
+                    "requires `gateway.total_shards` to be set",
+                );
+                Sharding::Auto
+            }
+        }
+    }
+
+    /// Reads the `[xkcd]` table.
+    fn xkcd(&mut self) -> XkcdConfig {
+        let default_url = Url::parse("https://xkcd.com/").unwrap();
+        let base_url = self.optional::<Url>("xkcd.base_url").unwrap_or(default_url);
+        if !matches!(base_url.scheme(), "http" | "https") {
+            self.invalid("xkcd.base_url", "must be an `http://` or `https://` URL");
+        }
+        let cache_size = self
+            .optional::<NonZeroUsize>("xkcd.cache_size")
+            .unwrap_or(NonZeroUsize::new(1000).unwrap());
+        let latest_ttl_secs = self.optional::<u64>("xkcd.latest_ttl_secs").unwrap_or(900);
+        let timeout_secs = self.optional::<u64>("xkcd.timeout_secs").unwrap_or(10);
+        if timeout_secs == 0 {
+            self.invalid("xkcd.timeout_secs", "must be at least 1");
+        }
+        let retries = self.optional::<u32>("xkcd.retries").unwrap_or(2);
+        if retries > MAX_XKCD_RETRIES {
+            self.invalid(
+                "xkcd.retries",
+                format!("must be at most {MAX_XKCD_RETRIES}"),
+            );
+        }
+
+        XkcdConfig {
+            base_url,
+            cache_size,
+            latest_ttl: Duration::from_secs(latest_ttl_secs),
+            persist_cache: self.optional::<bool>("xkcd.persist_cache").unwrap_or(true),
+            timeout: Duration::from_secs(timeout_secs),
+            retries,
+        }
+    }
+
+    /// Reads the `[cooldowns."<command>"]` tables. These have no environment variables, as
+    /// their keys are command names.
+    fn cooldowns(&mut self) -> HashMap<String, CooldownRule> {
+        let commands: Vec<String> = self
+            .table
+            .get("cooldowns")
+            .and_then(Value::as_table)
+            .map(|table| table.keys().cloned().collect())
+            .unwrap_or_default();
+
+        let mut cooldowns = HashMap::new();
+        for command in commands {
+            let mut secs = |field: &str| {
+                self.optional::<u64>(&format!("cooldowns.{command}.{field}"))
+                    .filter(|&secs| secs > 0)
+                    .map(Duration::from_secs)
+            };
+            let rule = CooldownRule {
+                global: secs("global_secs"),
+                guild: secs("guild_secs"),
+                user: secs("user_secs"),
+                persist: self
+                    .optional::<bool>(&format!("cooldowns.{command}.persist"))
+                    .unwrap_or(false),
+            };
+            cooldowns.insert(command, rule);
+        }
+        cooldowns
+    }
+
+    fn invalid(&mut self, key: &str, message: impl Into<String>) {
+        self.errors.push((key.to_string(), message.into()));
+    }
+
+    /// Removes and returns the value at `key`, so that anything left over is unknown.
+    fn take(&mut self, key: &str) -> Option<Value> {
+        let (parent, leaf) = match key.rsplit_once('.') {
+            Some((parent, leaf)) => (parent.split('.').collect(), leaf),
+            None => (Vec::new(), key),
+        };
+        let mut table = &mut self.table;
+        for segment in parent {
+            table = table.get_mut(segment)?.as_table_mut()?;
+        }
+        table.remove(leaf)
+    }
+
+    fn reject_unknown_keys(&mut self) {
+        let mut unknown = Vec::new();
+        collect_leaves(&self.table, "", &mut unknown);
+        for key in unknown {
+            self.invalid(&key, "unknown key");
+        }
+    }
+}
+
+/// Collects the dotted paths of every non-table value left in `table`.
+fn collect_leaves(table: &Table, prefix: &str, out: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Table(inner) => collect_leaves(inner, &path, out),
+            _ => out.push(path),
+        }
+    }
+}
+
+/// Every invalid key found while loading the config.
+#[derive(Debug)]
+pub struct InvalidConfig(Vec<(String, String)>);
+
+impl Display for InvalidConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for (key, message) in &self.0 {
+            let var = KEYS
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, var)| format!(" (env: {var})"))
+                .unwrap_or_default();
+            write!(f, "\n  - `{key}`{var}: {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidConfig {}
@@ -0,0 +1,349 @@
+//! Per-command cooldowns, shared by everyone, a guild, or a single user.
+//!
+//! Cooldowns are tracked in memory. Rules with `persist` set are also written to Postgres
+//! and loaded back on startup, so long cooldowns survive a restart.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use poise::serenity_prelude::{GuildId, UserId};
+use sqlx::PgPool;
+
+use crate::metrics::time_query;
+
+/// Cooldowns used for commands that aren't configured.
+const DEFAULT_RULES: &[(&str, CooldownRule)] = &[
+    (
+        "xkcd random",
+        CooldownRule {
+            user: Some(Duration::from_secs(5)),
+            ..CooldownRule::NONE
+        },
+    ),
+    (
+        "give",
+        CooldownRule {
+            user: Some(Duration::from_secs(10)),
+            ..CooldownRule::NONE
+        },
+    ),
+];
+
+/// Expired cooldowns are swept once this many are being tracked.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// How often a command can be used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CooldownRule {
+    /// Between uses by anyone.
+    pub global: Option<Duration>,
+    /// Between uses in the same guild.
+    pub guild: Option<Duration>,
+    /// Between uses by the same user.
+    pub user: Option<Duration>,
+    /// Whether to store the cooldown in Postgres.
+    pub persist: bool,
+}
+
+impl CooldownRule {
+    const NONE: Self = Self {
+        global: None,
+        guild: None,
+        user: None,
+        persist: false,
+    };
+}
+
+/// Who shares a cooldown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Bucket {
+    Global,
+    Guild(GuildId),
+    User(UserId),
+}
+
+impl Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Guild(guild_id) => write!(f, "guild:{guild_id}"),
+            Self::User(user_id) => write!(f, "user:{user_id}"),
+        }
+    }
+}
+
+impl FromStr for Bucket {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_id = |id: &str| id.parse::<u64>().ok().filter(|&id| id != 0).ok_or(());
+        match s.split_once(':') {
+            None if s == "global" => Ok(Self::Global),
+            Some(("guild", id)) => Ok(Self::Guild(GuildId::new(parse_id(id)?))),
+            Some(("user", id)) => Ok(Self::User(UserId::new(parse_id(id)?))),
+            _ => Err(()),
+        }
+    }
+}
+
+pub struct Cooldowns {
+    rules: HashMap<String, CooldownRule>,
+    expiries: Mutex<HashMap<(String, Bucket), SystemTime>>,
+    db: PgPool,
+}
+
+impl Cooldowns {
+    /// Applies `configured` rules on top of the defaults, and loads persisted cooldowns that
+    /// haven't expired yet, deleting the rest.
+    pub async fn load(configured: HashMap<String, CooldownRule>, db: PgPool) -> sqlx::Result<Self> {
+        let mut rules: HashMap<_, _> = DEFAULT_RULES
+            .iter()
+            .map(|(command, rule)| ((*command).to_string(), *rule))
+            .collect();
+        rules.extend(configured);
+
+        time_query(
+            "delete_expired_cooldowns",
+            sqlx::query!("DELETE FROM cooldowns WHERE expires_at <= now()").execute(&db),
+        )
+        .await?;
+        let rows = time_query(
+            "load_cooldowns",
+            sqlx::query!(
+                r#"
+                SELECT command, bucket, EXTRACT(EPOCH FROM expires_at)::bigint AS "expires_at!"
+                FROM cooldowns WHERE expires_at > now()
+                "#
+            )
+            .fetch_all(&db),
+        )
+        .await?;
+        let expiries = rows
+            .into_iter()
+            .filter_map(|row| {
+                let bucket = row.bucket.parse().ok()?;
+                let expires_at =
+                    UNIX_EPOCH + Duration::from_secs(u64::try_from(row.expires_at).ok()?);
+                Some(((row.command, bucket), expires_at))
+            })
+            .collect();
+
+        Ok(Self {
+            rules,
+            expiries: Mutex::new(expiries),
+            db,
+        })
+    }
+
+    /// Records a use of `command`, or returns when it can next be used if it's cooling down.
+    pub async fn hit(
+        &self,
+        command: &str,
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+    ) -> sqlx::Result<Option<SystemTime>> {
+        let Some(rule) = self.rules.get(command) else {
+            return Ok(None);
+        };
+
+        let started = match self.hit_at(command, rule, guild_id, user_id, SystemTime::now()) {
+            Ok(started) => started,
+            Err(until) => return Ok(Some(until)),
+        };
+        if rule.persist {
+            for (bucket, expires_at) in started {
+                self.persist(command, bucket, expires_at).await?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Starts every cooldown the use falls under, returning them, unless one is already
+    /// running, in which case returns when the last one ends.
+    fn hit_at(
+        &self,
+        command: &str,
+        rule: &CooldownRule,
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+        now: SystemTime,
+    ) -> Result<Vec<(Bucket, SystemTime)>, SystemTime> {
+        let buckets = [
+            rule.global.map(|duration| (Bucket::Global, duration)),
+            rule.guild
+                .zip(guild_id)
+                .map(|(duration, guild_id)| (Bucket::Guild(guild_id), duration)),
+            rule.user.map(|duration| (Bucket::User(user_id), duration)),
+        ];
+
+        let mut expiries = self.expiries.lock().unwrap();
+        let until = buckets
+            .iter()
+            .flatten()
+            .filter_map(|(bucket, _)| expiries.get(&(command.to_string(), *bucket)))
+            .filter(|&&expires_at| expires_at > now)
+            .max();
+        if let Some(&until) = until {
+            return Err(until);
+        }
+
+        if expiries.len() >= SWEEP_THRESHOLD {
+            expiries.retain(|_, expires_at| *expires_at > now);
+        }
+        let started: Vec<_> = buckets
+            .into_iter()
+            .flatten()
+            .map(|(bucket, duration)| (bucket, now + duration))
+            .collect();
+        for &(bucket, expires_at) in &started {
+            expiries.insert((command.to_string(), bucket), expires_at);
+        }
+        Ok(started)
+    }
+
+    async fn persist(
+        &self,
+        command: &str,
+        bucket: Bucket,
+        expires_at: SystemTime,
+    ) -> sqlx::Result<()> {
+        let expires_at = expires_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        time_query(
+            "persist_cooldown",
+            sqlx::query!(
+                "
+                INSERT INTO cooldowns (command, bucket, expires_at)
+                VALUES ($1, $2, to_timestamp($3))
+                ON CONFLICT (command, bucket) DO UPDATE SET expires_at = EXCLUDED.expires_at
+                ",
+                command,
+                bucket.to_string(),
+                expires_at
+            )
+            .execute(&self.db),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{GUILD, OTHER_USER, USER};
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    async fn cooldowns(db: PgPool, command: &str, rule: CooldownRule) -> Cooldowns {
+        Cooldowns::load(HashMap::from([(command.to_string(), rule)]), db)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn user_cooldowns_are_per_user(db: PgPool) {
+        let rule = CooldownRule {
+            user: Some(10 * SECOND),
+            ..CooldownRule::NONE
+        };
+        let cooldowns = cooldowns(db, "test", rule).await;
+        let now = SystemTime::now();
+
+        assert!(cooldowns
+            .hit_at("test", &rule, Some(GUILD), USER, now)
+            .is_ok());
+        assert_eq!(
+            cooldowns.hit_at("test", &rule, Some(GUILD), USER, now + SECOND),
+            Err(now + 10 * SECOND)
+        );
+        assert!(cooldowns
+            .hit_at("test", &rule, Some(GUILD), OTHER_USER, now + SECOND)
+            .is_ok());
+        assert!(cooldowns
+            .hit_at("test", &rule, Some(GUILD), USER, now + 10 * SECOND)
+            .is_ok());
+    }
+
+    #[sqlx::test]
+    async fn the_longest_running_cooldown_wins(db: PgPool) {
+        let rule = CooldownRule {
+            global: Some(5 * SECOND),
+            guild: Some(60 * SECOND),
+            ..CooldownRule::NONE
+        };
+        let cooldowns = cooldowns(db, "test", rule).await;
+        let now = SystemTime::now();
+
+        assert!(cooldowns
+            .hit_at("test", &rule, Some(GUILD), USER, now)
+            .is_ok());
+        // Another guild only waits for the global cooldown
+        assert_eq!(
+            cooldowns.hit_at("test", &rule, Some(GuildId::new(2)), USER, now),
+            Err(now + 5 * SECOND)
+        );
+        assert_eq!(
+            cooldowns.hit_at("test", &rule, Some(GUILD), OTHER_USER, now + 6 * SECOND),
+            Err(now + 60 * SECOND)
+        );
+        // Guild cooldowns don't apply in DMs
+        assert!(cooldowns
+            .hit_at("test", &rule, None, OTHER_USER, now + 6 * SECOND)
+            .is_ok());
+    }
+
+    #[sqlx::test]
+    async fn persisted_cooldowns_survive_a_restart(db: PgPool) {
+        let rule = CooldownRule {
+            user: Some(60 * 60 * SECOND),
+            persist: true,
+            ..CooldownRule::NONE
+        };
+
+        let first = cooldowns(db.clone(), "work", rule).await;
+        assert_eq!(first.hit("work", Some(GUILD), USER).await.unwrap(), None);
+
+        let second = cooldowns(db, "work", rule).await;
+        assert!(second
+            .hit("work", Some(GUILD), USER)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            second.hit("work", Some(GUILD), OTHER_USER).await.unwrap(),
+            None
+        );
+    }
+
+    #[sqlx::test]
+    async fn expired_cooldowns_are_deleted_on_load(db: PgPool) {
+        sqlx::query(
+            "INSERT INTO cooldowns (command, bucket, expires_at) VALUES ('work', 'global', now() - interval '1 hour')",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        cooldowns(db.clone(), "work", CooldownRule::NONE).await;
+        let remaining: i64 = sqlx::query_scalar("SELECT count(*) FROM cooldowns")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn buckets_round_trip() {
+        for bucket in [Bucket::Global, Bucket::Guild(GUILD), Bucket::User(USER)] {
+            assert_eq!(bucket.to_string().parse(), Ok(bucket));
+        }
+        assert_eq!("user:0".parse::<Bucket>(), Err(()));
+    }
+}
@@ -0,0 +1,602 @@
+//! Exporting a guild's economy, and importing it back (possibly from another bot).
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+
+use poise::serenity_prelude::{GuildId, UserId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use super::Balances;
+use crate::metrics::time_query;
+
+/// A guild's balances and jobs, as exported by `/ecoadmin export`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    pub users: Vec<Account>,
+    #[serde(default)]
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub user_id: UserId,
+    pub wallet_balance: i32,
+    pub bank_balance: i32,
+    #[serde(default)]
+    pub job: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    #[serde(rename = "job_id")]
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub salary_per_hour: i32,
+}
+
+impl Account {
+    fn balances(&self) -> Balances {
+        Balances {
+            wallet: self.wallet_balance,
+            bank: self.bank_balance,
+        }
+    }
+}
+
+/// Column names other bots use, normalized by [`normalize`].
+const USER_ID_COLUMNS: &[&str] = &["userid", "id", "memberid", "discordid"];
+const WALLET_COLUMNS: &[&str] = &[
+    "walletbalance",
+    "wallet",
+    "cash",
+    "balance",
+    "coins",
+    "money",
+];
+const BANK_COLUMNS: &[&str] = &["bankbalance", "bank"];
+const JOB_COLUMNS: &[&str] = &["job"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError(pub String);
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Lowercases a column name and drops spaces, dashes and underscores, so `User ID` and
+/// `user_id` match.
+fn normalize(column: &str) -> String {
+    column
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Backup {
+    pub fn to_json(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec_pretty(self)
+    }
+
+    /// Writes the balances and the jobs as two CSV files.
+    pub fn to_csv(&self) -> csv::Result<(Vec<u8>, Vec<u8>)> {
+        let mut users = csv::Writer::from_writer(Vec::new());
+        for account in &self.users {
+            users.serialize(account)?;
+        }
+        let mut jobs = csv::Writer::from_writer(Vec::new());
+        for job in &self.jobs {
+            jobs.serialize(job)?;
+        }
+
+        let into_inner = |writer: csv::Writer<Vec<u8>>| {
+            writer
+                .into_inner()
+                .map_err(|err| csv::Error::from(err.into_error()))
+        };
+        Ok((into_inner(users)?, into_inner(jobs)?))
+    }
+
+    /// Reads an Avion JSON export, or a JSON array of accounts from another bot.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, ParseError> {
+        let value: Value = serde_json::from_slice(bytes)
+            .map_err(|err| ParseError(format!("Invalid JSON: {err}")))?;
+        let backup = match value {
+            Value::Object(ref object) if object.contains_key("users") => {
+                serde_json::from_value(value)
+                    .map_err(|err| ParseError(format!("Invalid Avion export: {err}")))?
+            }
+            Value::Array(rows) => {
+                let rows = rows
+                    .into_iter()
+                    .map(|row| match row {
+                        Value::Object(object) => Ok(object
+                            .into_iter()
+                            .map(|(key, value)| {
+                                let value = match value {
+                                    Value::String(s) => s,
+                                    other => other.to_string(),
+                                };
+                                (normalize(&key), value)
+                            })
+                            .collect()),
+                        _ => Err(ParseError("Expected an array of objects".to_string())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self {
+                    users: accounts_from_rows(&rows)?,
+                    jobs: Vec::new(),
+                }
+            }
+            _ => {
+                return Err(ParseError(
+                    "Expected an Avion export or an array of accounts".to_string(),
+                ))
+            }
+        };
+
+        backup.validate()?;
+        Ok(backup)
+    }
+
+    /// Reads balances from a CSV file, matching columns by the names common bots use.
+    /// Files with a `job_id` column are read as jobs instead.
+    pub fn from_csv(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut reader = csv::Reader::from_reader(bytes);
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(|err| ParseError(format!("Invalid CSV: {err}")))?
+            .iter()
+            .map(normalize)
+            .collect();
+
+        let backup = if headers.iter().any(|header| header == "jobid") {
+            let jobs = csv::Reader::from_reader(bytes)
+                .deserialize()
+                .collect::<Result<_, _>>()
+                .map_err(|err| ParseError(format!("Invalid jobs CSV: {err}")))?;
+            Self {
+                users: Vec::new(),
+                jobs,
+            }
+        } else {
+            let rows = reader
+                .records()
+                .map(|record| {
+                    let record = record.map_err(|err| ParseError(format!("Invalid CSV: {err}")))?;
+                    Ok(headers
+                        .iter()
+                        .cloned()
+                        .zip(record.iter().map(str::to_string))
+                        .collect())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Self {
+                users: accounts_from_rows(&rows)?,
+                jobs: Vec::new(),
+            }
+        };
+
+        backup.validate()?;
+        Ok(backup)
+    }
+
+    fn validate(&self) -> Result<(), ParseError> {
+        let mut seen = HashSet::new();
+        for account in &self.users {
+            if !seen.insert(account.user_id) {
+                return Err(ParseError(format!(
+                    "User {} appears more than once",
+                    account.user_id
+                )));
+            }
+            if account.wallet_balance < 0 || account.bank_balance < 0 {
+                return Err(ParseError(format!(
+                    "User {} has a negative balance",
+                    account.user_id
+                )));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for job in &self.jobs {
+            if !seen.insert(&job.id) {
+                return Err(ParseError(format!(
+                    "Job `{}` appears more than once",
+                    job.id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maps rows of normalized column names to accounts.
+fn accounts_from_rows(rows: &[HashMap<String, String>]) -> Result<Vec<Account>, ParseError> {
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    // JSON rows may leave out columns, so look for each one across every row
+    let find = |aliases: &[&str]| {
+        aliases
+            .iter()
+            .find(|alias| rows.iter().any(|row| row.contains_key(**alias)))
+            .map(|alias| (*alias).to_string())
+    };
+    let user_column = find(USER_ID_COLUMNS).ok_or_else(|| {
+        ParseError("Couldn't find a user ID column, such as `user_id`".to_string())
+    })?;
+    let wallet_column = find(WALLET_COLUMNS);
+    let bank_column = find(BANK_COLUMNS);
+    if wallet_column.is_none() && bank_column.is_none() {
+        return Err(ParseError(
+            "Couldn't find a balance column, such as `wallet` or `bank`".to_string(),
+        ));
+    }
+    let job_column = find(JOB_COLUMNS);
+
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let line = i + 1;
+            let amount = |column: &Option<String>| -> Result<i32, ParseError> {
+                let Some(value) = column.as_ref().and_then(|column| row.get(column)) else {
+                    return Ok(0);
+                };
+                value.trim().parse().map_err(|err| {
+                    ParseError(format!(
+                        "Row {line}: `{value}` is not a valid amount: {err}"
+                    ))
+                })
+            };
+
+            let raw_id = row.get(&user_column).map_or("", |id| id.trim());
+            let user_id = raw_id
+                .parse::<u64>()
+                .ok()
+                .filter(|&id| id != 0)
+                .map(UserId::new)
+                .ok_or_else(|| {
+                    ParseError(format!("Row {line}: `{raw_id}` is not a valid user ID"))
+                })?;
+
+            Ok(Account {
+                user_id,
+                wallet_balance: amount(&wallet_column)?,
+                bank_balance: amount(&bank_column)?,
+                job: job_column
+                    .as_ref()
+                    .and_then(|column| row.get(column))
+                    .filter(|job| !job.is_empty())
+                    .cloned(),
+            })
+        })
+        .collect()
+}
+
+/// What an import would change.
+#[derive(Debug, Default, PartialEq)]
+pub struct Diff {
+    pub added: Vec<(UserId, Balances)>,
+    /// Accounts whose balances change, with the old and new balances.
+    pub changed: Vec<(UserId, Balances, Balances)>,
+    /// Accounts whose balances stay the same, but whose job changes.
+    pub reassigned: usize,
+    pub unchanged: usize,
+    pub jobs_added: usize,
+    pub jobs_changed: usize,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.changed.is_empty()
+            && self.reassigned == 0
+            && self.jobs_added == 0
+            && self.jobs_changed == 0
+    }
+}
+
+/// Compares an import against the guild's current data. Accounts and jobs missing from the
+/// import are left alone, and so are the jobs of accounts imported without one.
+pub fn diff(current: &Backup, import: &Backup) -> Diff {
+    let accounts: HashMap<_, _> = current
+        .users
+        .iter()
+        .map(|account| (account.user_id, account))
+        .collect();
+    let jobs: HashMap<_, _> = current.jobs.iter().map(|job| (&job.id, job)).collect();
+
+    let mut diff = Diff::default();
+    for account in &import.users {
+        match accounts.get(&account.user_id) {
+            None => diff.added.push((account.user_id, account.balances())),
+            Some(old) if old.balances() != account.balances() => {
+                diff.changed
+                    .push((account.user_id, old.balances(), account.balances()));
+            }
+            Some(old) if account.job.is_some() && account.job != old.job => diff.reassigned += 1,
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    for job in &import.jobs {
+        match jobs.get(&job.id) {
+            None => diff.jobs_added += 1,
+            Some(old) if *old == job => {}
+            Some(_) => diff.jobs_changed += 1,
+        }
+    }
+    diff
+}
+
+/// Reads every account and job in the guild.
+pub async fn export(db: &PgPool, guild_id: GuildId) -> sqlx::Result<Backup> {
+    let users = time_query(
+        "economy_export_users",
+        sqlx::query!(
+            "
+            SELECT user_id, wallet_balance, bank_balance, job
+            FROM users WHERE guild_id = $1
+            ORDER BY user_id
+            ",
+            i64::from(guild_id)
+        )
+        .fetch_all(db),
+    )
+    .await?;
+    let jobs = time_query(
+        "economy_export_jobs",
+        sqlx::query_as!(
+            Job,
+            "
+            SELECT job_id AS id, name, description, salary_per_hour
+            FROM jobs WHERE guild_id = $1
+            ORDER BY job_id
+            ",
+            i64::from(guild_id)
+        )
+        .fetch_all(db),
+    )
+    .await?;
+
+    Ok(Backup {
+        users: users
+            .into_iter()
+            .filter_map(|row| {
+                Some(Account {
+                    user_id: UserId::new(u64::try_from(row.user_id).ok()?),
+                    wallet_balance: row.wallet_balance,
+                    bank_balance: row.bank_balance,
+                    job: row.job,
+                })
+            })
+            .collect(),
+        jobs,
+    })
+}
+
+/// Writes every account and job in the import, all or nothing.
+pub async fn import(db: &PgPool, guild_id: GuildId, backup: &Backup) -> sqlx::Result<()> {
+    let user_ids: Vec<i64> = backup.users.iter().map(|a| i64::from(a.user_id)).collect();
+    let wallets: Vec<i32> = backup.users.iter().map(|a| a.wallet_balance).collect();
+    let banks: Vec<i32> = backup.users.iter().map(|a| a.bank_balance).collect();
+    let user_jobs: Vec<Option<String>> = backup.users.iter().map(|a| a.job.clone()).collect();
+    let job_ids: Vec<String> = backup.jobs.iter().map(|j| j.id.clone()).collect();
+    let names: Vec<String> = backup.jobs.iter().map(|j| j.name.clone()).collect();
+    let descriptions: Vec<String> = backup.jobs.iter().map(|j| j.description.clone()).collect();
+    let salaries: Vec<i32> = backup.jobs.iter().map(|j| j.salary_per_hour).collect();
+
+    time_query("economy_import", async {
+        let mut transaction = db.begin().await?;
+
+        sqlx::query!(
+            "
+            INSERT INTO users (guild_id, user_id, wallet_balance, bank_balance, job)
+            SELECT $1, * FROM UNNEST($2::bigint[], $3::int[], $4::int[], $5::text[])
+            ON CONFLICT (user_id, guild_id) DO UPDATE SET
+                wallet_balance = EXCLUDED.wallet_balance,
+                bank_balance = EXCLUDED.bank_balance,
+                job = COALESCE(EXCLUDED.job, users.job)
+            ",
+            i64::from(guild_id),
+            &user_ids,
+            &wallets,
+            &banks,
+            &user_jobs as &[Option<String>]
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+            INSERT INTO jobs (guild_id, job_id, name, description, salary_per_hour)
+            SELECT $1, * FROM UNNEST($2::text[], $3::text[], $4::text[], $5::int[])
+            ON CONFLICT (guild_id, job_id) DO UPDATE SET
+                name = EXCLUDED.name,
+                description = EXCLUDED.description,
+                salary_per_hour = EXCLUDED.salary_per_hour
+            ",
+            i64::from(guild_id),
+            &job_ids,
+            &names,
+            &descriptions,
+            &salaries
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{balances, insert_user, GUILD, OTHER_USER, USER};
+
+    fn account(user_id: UserId, wallet_balance: i32, bank_balance: i32) -> Account {
+        Account {
+            user_id,
+            wallet_balance,
+            bank_balance,
+            job: None,
+        }
+    }
+
+    #[test]
+    fn reads_csv_from_other_bots() {
+        let csv = "Rank,User,User ID,Cash,Bank,Total\n1,someone,10,100,50,150\n2,other,20,5,0,5\n";
+
+        let backup = Backup::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(
+            backup.users,
+            vec![account(USER, 100, 50), account(OTHER_USER, 5, 0)]
+        );
+    }
+
+    #[test]
+    fn reads_json_arrays_from_other_bots() {
+        let json = r#"[{"id": "10", "balance": 7}, {"id": 20, "balance": 3, "bank": 1}]"#;
+
+        let backup = Backup::from_json(json.as_bytes()).unwrap();
+        assert_eq!(
+            backup.users,
+            vec![account(USER, 7, 0), account(OTHER_USER, 3, 1)]
+        );
+    }
+
+    #[test]
+    fn round_trips_avion_exports() {
+        let backup = Backup {
+            users: vec![Account {
+                job: Some("chef".to_string()),
+                ..account(USER, 1, 2)
+            }],
+            jobs: vec![Job {
+                id: "chef".to_string(),
+                name: "Chef".to_string(),
+                description: "Cooks".to_string(),
+                salary_per_hour: 10,
+            }],
+        };
+
+        assert_eq!(
+            Backup::from_json(&backup.to_json().unwrap()).unwrap(),
+            backup
+        );
+        let (users, jobs) = backup.to_csv().unwrap();
+        assert_eq!(Backup::from_csv(&users).unwrap().users, backup.users);
+        assert_eq!(Backup::from_csv(&jobs).unwrap().jobs, backup.jobs);
+    }
+
+    #[test]
+    fn rejects_bad_rows() {
+        assert_eq!(
+            Backup::from_csv(b"user_id,wallet\nabc,5\n"),
+            Err(ParseError(
+                "Row 1: `abc` is not a valid user ID".to_string()
+            ))
+        );
+        assert_eq!(
+            Backup::from_csv(b"user_id,wallet\n10,5\n10,6\n"),
+            Err(ParseError("User 10 appears more than once".to_string()))
+        );
+        assert_eq!(
+            Backup::from_csv(b"user_id,wallet\n10,-5\n"),
+            Err(ParseError("User 10 has a negative balance".to_string()))
+        );
+        assert!(Backup::from_csv(b"name,level\nsomeone,5\n").is_err());
+    }
+
+    #[test]
+    fn diffs_against_current_data() {
+        let chef = |account| Account {
+            job: Some("chef".to_string()),
+            ..account
+        };
+        let current = Backup {
+            users: vec![
+                account(USER, 10, 0),
+                account(UserId::new(30), 1, 1),
+                account(UserId::new(40), 1, 1),
+                chef(account(UserId::new(50), 1, 1)),
+            ],
+            jobs: Vec::new(),
+        };
+        let import = Backup {
+            users: vec![
+                account(USER, 20, 0),
+                account(OTHER_USER, 5, 0),
+                account(UserId::new(30), 1, 1),
+                chef(account(UserId::new(40), 1, 1)),
+                account(UserId::new(50), 1, 1),
+            ],
+            jobs: Vec::new(),
+        };
+
+        let diff = diff(&current, &import);
+        assert_eq!(
+            diff.added,
+            vec![(OTHER_USER, Balances { wallet: 5, bank: 0 })]
+        );
+        assert_eq!(
+            diff.changed,
+            vec![(
+                USER,
+                Balances {
+                    wallet: 10,
+                    bank: 0
+                },
+                Balances {
+                    wallet: 20,
+                    bank: 0
+                }
+            )]
+        );
+        assert_eq!(diff.reassigned, 1);
+        assert_eq!(diff.unchanged, 2);
+    }
+
+    #[sqlx::test]
+    async fn imports_and_exports_a_guild(db: PgPool) {
+        insert_user(&db, USER, GUILD, 10, 0).await;
+        insert_user(&db, USER, GuildId::new(2), 99, 0).await;
+        let import = Backup {
+            users: vec![account(USER, 20, 5), account(OTHER_USER, 5, 0)],
+            jobs: Vec::new(),
+        };
+
+        super::import(&db, GUILD, &import).await.unwrap();
+        assert_eq!(export(&db, GUILD).await.unwrap(), import);
+        assert_eq!(balances(&db, USER, GuildId::new(2)).await, (99, 0));
+    }
+
+    #[sqlx::test]
+    async fn keeps_jobs_missing_from_imports(db: PgPool) {
+        let backup = Backup {
+            users: vec![Account {
+                job: Some("chef".to_string()),
+                ..account(USER, 10, 0)
+            }],
+            jobs: Vec::new(),
+        };
+        super::import(&db, GUILD, &backup).await.unwrap();
+
+        let import = Backup {
+            users: vec![account(USER, 20, 0)],
+            jobs: Vec::new(),
+        };
+        super::import(&db, GUILD, &import).await.unwrap();
+        let exported = export(&db, GUILD).await.unwrap();
+        assert_eq!(exported.users[0].job.as_deref(), Some("chef"));
+        assert_eq!(exported.users[0].wallet_balance, 20);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use poise::serenity_prelude::{GuildId, UserId};
+
+use super::{Balances, Store};
+
+/// Keeps accounts in memory, for testing the economy without a database.
+#[derive(Default)]
+pub struct MemoryStore {
+    accounts: Mutex<HashMap<(GuildId, UserId), Balances>>,
+}
+
+impl MemoryStore {
+    /// Creates a store holding `(user, wallet, bank)` accounts in one guild.
+    pub fn with_accounts(guild_id: GuildId, accounts: &[(UserId, i32, i32)]) -> Self {
+        let store = Self::default();
+        for &(user_id, wallet, bank) in accounts {
+            store.set(guild_id, user_id, Balances { wallet, bank });
+        }
+        store
+    }
+
+    pub fn set(&self, guild_id: GuildId, user_id: UserId, balances: Balances) {
+        self.accounts
+            .lock()
+            .unwrap()
+            .insert((guild_id, user_id), balances);
+    }
+}
+
+impl Store for MemoryStore {
+    async fn balances(&self, guild_id: GuildId, user_id: UserId) -> sqlx::Result<Option<Balances>> {
+        Ok(self
+            .accounts
+            .lock()
+            .unwrap()
+            .get(&(guild_id, user_id))
+            .copied())
+    }
+
+    async fn create_account(&self, guild_id: GuildId, user_id: UserId) -> sqlx::Result<bool> {
+        let mut accounts = self.accounts.lock().unwrap();
+        if accounts.contains_key(&(guild_id, user_id)) {
+            return Ok(false);
+        }
+        accounts.insert((guild_id, user_id), Balances::default());
+        Ok(true)
+    }
+
+    async fn transfer(
+        &self,
+        guild_id: GuildId,
+        from: UserId,
+        to: UserId,
+        amount: i32,
+    ) -> sqlx::Result<Option<(Balances, Balances)>> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let (Some(&from_balances), Some(&to_balances)) = (
+            accounts.get(&(guild_id, from)),
+            accounts.get(&(guild_id, to)),
+        ) else {
+            return Ok(None);
+        };
+        if from_balances.wallet < amount {
+            return Ok(None);
+        }
+
+        let from_balances = Balances {
+            wallet: from_balances.wallet - amount,
+            ..from_balances
+        };
+        let to_balances = Balances {
+            wallet: to_balances.wallet + amount,
+            ..to_balances
+        };
+        accounts.insert((guild_id, from), from_balances);
+        accounts.insert((guild_id, to), to_balances);
+        Ok(Some((from_balances, to_balances)))
+    }
+
+    async fn move_to_bank(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        amount: i32,
+    ) -> sqlx::Result<Option<Balances>> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let Some(balances) = accounts.get_mut(&(guild_id, user_id)) else {
+            return Ok(None);
+        };
+        if balances.wallet < amount || balances.bank < -amount {
+            return Ok(None);
+        }
+
+        balances.wallet -= amount;
+        balances.bank += amount;
+        Ok(Some(*balances))
+    }
+}
@@ -0,0 +1,345 @@
+//! The server economy, independent of Discord's types beyond IDs.
+//!
+//! Commands call into [`EconomyService`] and only render its results, so the rules here can
+//! be tested against [`MemoryStore`] without a database.
+
+use std::fmt::{self, Display};
+
+use poise::serenity_prelude::{GuildId, UserId};
+
+pub mod backup;
+#[cfg(test)]
+mod memory;
+mod postgres;
+mod store;
+
+#[cfg(test)]
+pub use memory::MemoryStore;
+pub use postgres::PgStore;
+pub use store::Store;
+
+/// A user's balances in one guild.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Balances {
+    pub wallet: i32,
+    pub bank: i32,
+}
+
+/// The balances of both sides of a transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: Balances,
+    pub to: Balances,
+}
+
+#[derive(Debug)]
+pub enum EconomyError {
+    NotRegistered(UserId),
+    AlreadyRegistered,
+    /// Amounts must be positive.
+    InvalidAmount,
+    TransferToSelf,
+    /// The user needs `shortfall` more coins to move `amount`.
+    InsufficientFunds {
+        shortfall: i32,
+        amount: i32,
+    },
+    Database(sqlx::Error),
+}
+
+impl Display for EconomyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotRegistered(user_id) => write!(f, "user {user_id} is not registered"),
+            Self::AlreadyRegistered => write!(f, "user is already registered"),
+            Self::InvalidAmount => write!(f, "amount must be positive"),
+            Self::TransferToSelf => write!(f, "cannot transfer to yourself"),
+            Self::InsufficientFunds { shortfall, amount } => {
+                write!(
+                    f,
+                    "insufficient funds ({shortfall} more needed for {amount})"
+                )
+            }
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EconomyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for EconomyError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Database(err)
+    }
+}
+
+pub struct EconomyService<S> {
+    store: S,
+}
+
+impl<S: Store> EconomyService<S> {
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    pub async fn register(&self, guild_id: GuildId, user_id: UserId) -> Result<(), EconomyError> {
+        if self.store.create_account(guild_id, user_id).await? {
+            Ok(())
+        } else {
+            Err(EconomyError::AlreadyRegistered)
+        }
+    }
+
+    pub async fn get_balances(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Balances, EconomyError> {
+        self.store
+            .balances(guild_id, user_id)
+            .await?
+            .ok_or(EconomyError::NotRegistered(user_id))
+    }
+
+    /// Works out what a transfer would leave both users with, without performing it.
+    pub async fn preview_transfer(
+        &self,
+        guild_id: GuildId,
+        from: UserId,
+        to: UserId,
+        amount: i32,
+    ) -> Result<Transfer, EconomyError> {
+        if amount <= 0 {
+            return Err(EconomyError::InvalidAmount);
+        }
+        if from == to {
+            return Err(EconomyError::TransferToSelf);
+        }
+
+        let from_balances = self.get_balances(guild_id, from).await?;
+        let to_balances = self.get_balances(guild_id, to).await?;
+        if from_balances.wallet < amount {
+            return Err(EconomyError::InsufficientFunds {
+                shortfall: amount - from_balances.wallet,
+                amount,
+            });
+        }
+
+        Ok(Transfer {
+            from: Balances {
+                wallet: from_balances.wallet - amount,
+                ..from_balances
+            },
+            to: Balances {
+                wallet: to_balances.wallet + amount,
+                ..to_balances
+            },
+        })
+    }
+
+    /// Moves `amount` coins from one user's wallet to another's.
+    pub async fn transfer(
+        &self,
+        guild_id: GuildId,
+        from: UserId,
+        to: UserId,
+        amount: i32,
+    ) -> Result<Transfer, EconomyError> {
+        self.preview_transfer(guild_id, from, to, amount).await?;
+
+        match self.store.transfer(guild_id, from, to, amount).await? {
+            Some((from, to)) => Ok(Transfer { from, to }),
+            // Balances changed since the preview, so explain why it failed now
+            None => Err(self
+                .preview_transfer(guild_id, from, to, amount)
+                .await
+                .err()
+                .unwrap_or(EconomyError::InsufficientFunds {
+                    shortfall: amount,
+                    amount,
+                })),
+        }
+    }
+
+    /// Moves `amount` coins from the user's wallet into their bank.
+    #[allow(dead_code)] // Not exposed as a command yet
+    pub async fn deposit(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        amount: i32,
+    ) -> Result<Balances, EconomyError> {
+        let balances = self.get_balances(guild_id, user_id).await?;
+        if amount <= 0 {
+            return Err(EconomyError::InvalidAmount);
+        }
+        if balances.wallet < amount {
+            return Err(EconomyError::InsufficientFunds {
+                shortfall: amount - balances.wallet,
+                amount,
+            });
+        }
+
+        self.move_to_bank(guild_id, user_id, amount).await
+    }
+
+    /// Moves `amount` coins from the user's bank into their wallet.
+    #[allow(dead_code)] // Not exposed as a command yet
+    pub async fn withdraw(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        amount: i32,
+    ) -> Result<Balances, EconomyError> {
+        let balances = self.get_balances(guild_id, user_id).await?;
+        if amount <= 0 {
+            return Err(EconomyError::InvalidAmount);
+        }
+        if balances.bank < amount {
+            return Err(EconomyError::InsufficientFunds {
+                shortfall: amount - balances.bank,
+                amount,
+            });
+        }
+
+        self.move_to_bank(guild_id, user_id, -amount).await
+    }
+
+    async fn move_to_bank(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        amount: i32,
+    ) -> Result<Balances, EconomyError> {
+        self.store
+            .move_to_bank(guild_id, user_id, amount)
+            .await?
+            .ok_or(EconomyError::InsufficientFunds {
+                shortfall: amount.abs(),
+                amount: amount.abs(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{GUILD, OTHER_USER, USER};
+
+    fn service_with(accounts: &[(UserId, i32, i32)]) -> EconomyService<MemoryStore> {
+        EconomyService::new(MemoryStore::with_accounts(GUILD, accounts))
+    }
+
+    #[tokio::test]
+    async fn register_creates_an_empty_account_once() {
+        let economy = service_with(&[]);
+
+        economy.register(GUILD, USER).await.unwrap();
+        assert_eq!(
+            economy.get_balances(GUILD, USER).await.unwrap(),
+            Balances::default()
+        );
+        assert!(matches!(
+            economy.register(GUILD, USER).await,
+            Err(EconomyError::AlreadyRegistered)
+        ));
+    }
+
+    #[tokio::test]
+    async fn unregistered_users_have_no_balances() {
+        let economy = service_with(&[]);
+
+        assert!(matches!(
+            economy.get_balances(GUILD, USER).await,
+            Err(EconomyError::NotRegistered(user_id)) if user_id == USER
+        ));
+    }
+
+    #[tokio::test]
+    async fn transfer_moves_wallet_balances() {
+        let economy = service_with(&[(USER, 50, 7), (OTHER_USER, 5, 0)]);
+
+        let transfer = economy.transfer(GUILD, USER, OTHER_USER, 10).await.unwrap();
+        assert_eq!(
+            transfer.from,
+            Balances {
+                wallet: 40,
+                bank: 7
+            }
+        );
+        assert_eq!(
+            transfer.to,
+            Balances {
+                wallet: 15,
+                bank: 0
+            }
+        );
+        assert_eq!(
+            economy
+                .get_balances(GUILD, OTHER_USER)
+                .await
+                .unwrap()
+                .wallet,
+            15
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_is_validated() {
+        let economy = service_with(&[(USER, 5, 1000), (OTHER_USER, 0, 0)]);
+
+        assert!(matches!(
+            economy.transfer(GUILD, USER, OTHER_USER, 0).await,
+            Err(EconomyError::InvalidAmount)
+        ));
+        assert!(matches!(
+            economy.transfer(GUILD, USER, USER, 1).await,
+            Err(EconomyError::TransferToSelf)
+        ));
+        assert!(matches!(
+            economy.transfer(GUILD, USER, OTHER_USER, 10).await,
+            Err(EconomyError::InsufficientFunds {
+                shortfall: 5,
+                amount: 10
+            })
+        ));
+        assert!(matches!(
+            economy.transfer(GUILD, USER, UserId::new(99), 1).await,
+            Err(EconomyError::NotRegistered(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deposit_and_withdraw_move_between_wallet_and_bank() {
+        let economy = service_with(&[(USER, 50, 0)]);
+
+        assert_eq!(
+            economy.deposit(GUILD, USER, 30).await.unwrap(),
+            Balances {
+                wallet: 20,
+                bank: 30
+            }
+        );
+        assert_eq!(
+            economy.withdraw(GUILD, USER, 10).await.unwrap(),
+            Balances {
+                wallet: 30,
+                bank: 20
+            }
+        );
+        assert!(matches!(
+            economy.withdraw(GUILD, USER, 25).await,
+            Err(EconomyError::InsufficientFunds {
+                shortfall: 5,
+                amount: 25
+            })
+        ));
+    }
+}
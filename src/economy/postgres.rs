@@ -0,0 +1,207 @@
+use poise::serenity_prelude::{GuildId, UserId};
+use sqlx::PgPool;
+
+use super::{Balances, Store};
+use crate::metrics::time_query;
+
+/// Stores accounts in the `users` table.
+#[derive(Clone)]
+pub struct PgStore {
+    db: PgPool,
+}
+
+impl PgStore {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+impl Store for PgStore {
+    async fn balances(&self, guild_id: GuildId, user_id: UserId) -> sqlx::Result<Option<Balances>> {
+        let record = time_query(
+            "user_balances",
+            sqlx::query!(
+                "
+    SELECT bank_balance, wallet_balance
+    FROM users
+    WHERE user_id = $1 AND guild_id = $2
+        ",
+                i64::from(user_id),
+                i64::from(guild_id)
+            )
+            .fetch_optional(&self.db),
+        )
+        .await?;
+
+        Ok(record.map(|record| Balances {
+            wallet: record.wallet_balance,
+            bank: record.bank_balance,
+        }))
+    }
+
+    async fn create_account(&self, guild_id: GuildId, user_id: UserId) -> sqlx::Result<bool> {
+        let result = time_query(
+            "register_user",
+            sqlx::query!(
+                "
+            INSERT INTO users (user_id, guild_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            ",
+                i64::from(user_id),
+                i64::from(guild_id)
+            )
+            .execute(&self.db),
+        )
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn transfer(
+        &self,
+        guild_id: GuildId,
+        from: UserId,
+        to: UserId,
+        amount: i32,
+    ) -> sqlx::Result<Option<(Balances, Balances)>> {
+        time_query("transfer", async {
+            let mut transaction = self.db.begin().await?;
+
+            // Decrease sender's wallet balance, if they can afford it
+            let Some(from_balances) = sqlx::query_as!(
+                Balances,
+                "
+                UPDATE users SET wallet_balance = wallet_balance - $1
+                WHERE user_id = $2 AND guild_id = $3 AND wallet_balance >= $1
+                RETURNING wallet_balance AS wallet, bank_balance AS bank
+                ",
+                amount,
+                i64::from(from),
+                i64::from(guild_id)
+            )
+            .fetch_optional(&mut *transaction)
+            .await?
+            else {
+                return Ok(None);
+            };
+
+            // Increase receiver's wallet balance
+            let Some(to_balances) = sqlx::query_as!(
+                Balances,
+                "
+                UPDATE users SET wallet_balance = wallet_balance + $1
+                WHERE user_id = $2 AND guild_id = $3
+                RETURNING wallet_balance AS wallet, bank_balance AS bank
+                ",
+                amount,
+                i64::from(to),
+                i64::from(guild_id)
+            )
+            .fetch_optional(&mut *transaction)
+            .await?
+            else {
+                return Ok(None);
+            };
+
+            transaction.commit().await?;
+            Ok(Some((from_balances, to_balances)))
+        })
+        .await
+    }
+
+    async fn move_to_bank(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        amount: i32,
+    ) -> sqlx::Result<Option<Balances>> {
+        time_query(
+            "move_to_bank",
+            sqlx::query_as!(
+                Balances,
+                "
+                UPDATE users
+                SET wallet_balance = wallet_balance - $1, bank_balance = bank_balance + $1
+                WHERE user_id = $2 AND guild_id = $3
+                    AND wallet_balance >= $1 AND bank_balance >= -$1
+                RETURNING wallet_balance AS wallet, bank_balance AS bank
+                ",
+                amount,
+                i64::from(user_id),
+                i64::from(guild_id)
+            )
+            .fetch_optional(&self.db),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{balances, insert_user, GUILD, OTHER_USER, USER};
+
+    #[sqlx::test]
+    async fn create_account_only_once(db: PgPool) {
+        let store = PgStore::new(db.clone());
+
+        assert!(store.create_account(GUILD, USER).await.unwrap());
+        assert!(!store.create_account(GUILD, USER).await.unwrap());
+        assert_eq!(balances(&db, USER, GUILD).await, (0, 0));
+    }
+
+    #[sqlx::test]
+    async fn transfer_is_all_or_nothing(db: PgPool) {
+        let store = PgStore::new(db.clone());
+        insert_user(&db, USER, GUILD, 50, 0).await;
+
+        // The receiver isn't registered, so the sender keeps their coins
+        assert_eq!(
+            store.transfer(GUILD, USER, OTHER_USER, 10).await.unwrap(),
+            None
+        );
+        assert_eq!(balances(&db, USER, GUILD).await, (50, 0));
+
+        insert_user(&db, OTHER_USER, GUILD, 5, 0).await;
+        assert_eq!(
+            store.transfer(GUILD, USER, OTHER_USER, 60).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            store.transfer(GUILD, USER, OTHER_USER, 10).await.unwrap(),
+            Some((
+                Balances {
+                    wallet: 40,
+                    bank: 0
+                },
+                Balances {
+                    wallet: 15,
+                    bank: 0
+                }
+            ))
+        );
+    }
+
+    #[sqlx::test]
+    async fn move_to_bank_keeps_both_sides_positive(db: PgPool) {
+        let store = PgStore::new(db.clone());
+        insert_user(&db, USER, GUILD, 50, 0).await;
+
+        assert_eq!(
+            store.move_to_bank(GUILD, USER, 30).await.unwrap(),
+            Some(Balances {
+                wallet: 20,
+                bank: 30
+            })
+        );
+        assert_eq!(store.move_to_bank(GUILD, USER, -40).await.unwrap(), None);
+        assert_eq!(
+            store.move_to_bank(GUILD, USER, -30).await.unwrap(),
+            Some(Balances {
+                wallet: 50,
+                bank: 0
+            })
+        );
+    }
+}
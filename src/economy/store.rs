@@ -0,0 +1,45 @@
+use std::future::Future;
+
+use poise::serenity_prelude::{GuildId, UserId};
+
+use super::Balances;
+
+/// Persistence for economy accounts. Every method is atomic.
+pub trait Store: Send + Sync {
+    fn balances(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> impl Future<Output = sqlx::Result<Option<Balances>>> + Send;
+
+    /// Creates an empty account, returning `false` if one already exists.
+    fn create_account(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> impl Future<Output = sqlx::Result<bool>> + Send;
+
+    /// Moves `amount` from one wallet to the other, returning both accounts' new balances.
+    ///
+    /// Returns `None`, changing nothing, if either account is missing or the sender can't
+    /// afford it.
+    fn transfer(
+        &self,
+        guild_id: GuildId,
+        from: UserId,
+        to: UserId,
+        amount: i32,
+    ) -> impl Future<Output = sqlx::Result<Option<(Balances, Balances)>>> + Send;
+
+    /// Moves `amount` from the wallet to the bank (or back, if negative), returning the new
+    /// balances.
+    ///
+    /// Returns `None`, changing nothing, if the account is missing or either side would go
+    /// negative.
+    fn move_to_bank(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        amount: i32,
+    ) -> impl Future<Output = sqlx::Result<Option<Balances>>> + Send;
+}
@@ -1,27 +1,48 @@
-use poise::serenity_prelude::{Colour, CreateEmbed};
+use poise::serenity_prelude::{Colour, CreateEmbed, Timestamp};
 
-pub fn user_not_in_db() -> CreateEmbed {
+use crate::i18n::{tr, Locale};
+use crate::util::timestamp::{Format as TimestampFormat, TimestampExt};
+
+pub fn user_not_in_db(locale: Locale) -> CreateEmbed {
     CreateEmbed::new()
-        .title("User not found")
-        .description("This user may not have used Avion in this server before.")
+        .title(tr!(locale, "user-not-found-title"))
+        .description(tr!(locale, "user-not-found-description"))
         .field(
-            "Is this you?",
-            "If so, just run `/register` to get started.",
+            tr!(locale, "user-not-found-hint-title"),
+            tr!(locale, "user-not-found-hint"),
             false,
         )
         .colour(Colour::RED)
 }
 
-pub fn bots_not_allowed() -> CreateEmbed {
+pub fn bots_not_allowed(locale: Locale) -> CreateEmbed {
     CreateEmbed::new()
-        .title("Bots not allowed with this command")
-        .description("You can't use this command with bots.")
+        .title(tr!(locale, "bots-not-allowed-title"))
+        .description(tr!(locale, "bots-not-allowed-description"))
         .colour(Colour::RED)
 }
 
-pub fn cannot_use_yourself() -> CreateEmbed {
+pub fn cannot_use_yourself(locale: Locale) -> CreateEmbed {
     CreateEmbed::new()
-        .title("Wait a second...")
-        .description("You can't use this command with yourself!")
+        .title(tr!(locale, "cannot-use-yourself-title"))
+        .description(tr!(locale, "cannot-use-yourself-description"))
         .colour(Colour::RED)
 }
+
+pub fn bot_restarting(locale: Locale) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(tr!(locale, "bot-restarting-title"))
+        .description(tr!(locale, "bot-restarting-description"))
+        .colour(Colour::ORANGE)
+}
+
+pub fn on_cooldown(locale: Locale, until: Timestamp) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(tr!(locale, "on-cooldown-title"))
+        .description(tr!(
+            locale,
+            "on-cooldown-description",
+            when = until.to_discord_timestamp(TimestampFormat::Relative)
+        ))
+        .colour(Colour::ORANGE)
+}
@@ -0,0 +1,244 @@
+//! Deletes a guild's data some time after Avion is removed from it.
+//!
+//! Nothing is deleted straight away: the guild is only marked as removed, and the mark is
+//! cleared if Avion is added back before the grace period is over.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use poise::serenity_prelude::GuildId;
+use sqlx::PgPool;
+use tracing::{error, info};
+
+use crate::command_settings::GuildCommandSettings;
+use crate::i18n::GuildLocales;
+use crate::metrics::time_query;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Marks the guild's data for deletion. Being removed again keeps the original time.
+pub async fn mark_removed(db: &PgPool, guild_id: GuildId) -> sqlx::Result<()> {
+    time_query(
+        "mark_guild_removed",
+        sqlx::query!(
+            "INSERT INTO guild_removals (guild_id) VALUES ($1) ON CONFLICT DO NOTHING",
+            i64::from(guild_id)
+        )
+        .execute(db),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Cancels a pending deletion, returning whether there was one.
+pub async fn restore(db: &PgPool, guild_id: GuildId) -> sqlx::Result<bool> {
+    let result = time_query(
+        "restore_guild",
+        sqlx::query!(
+            "DELETE FROM guild_removals WHERE guild_id = $1",
+            i64::from(guild_id)
+        )
+        .execute(db),
+    )
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Deletes the data of every guild removed more than `grace_period` ago, returning the guilds
+/// that were purged. Guilds under a legal hold are kept until the hold is lifted.
+pub async fn purge_expired(db: &PgPool, grace_period: Duration) -> sqlx::Result<Vec<GuildId>> {
+    let grace_secs = i64::try_from(grace_period.as_secs()).unwrap_or(i64::MAX);
+
+    time_query("purge_removed_guilds", async {
+        let mut transaction = db.begin().await?;
+
+        let expired = sqlx::query_scalar!(
+            "
+            DELETE FROM guild_removals
+            WHERE removed_at < now() - $1::bigint * interval '1 second'
+                AND guild_id NOT IN (SELECT guild_id FROM legal_holds)
+            RETURNING guild_id
+            ",
+            grace_secs
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        sqlx::query!("DELETE FROM users WHERE guild_id = ANY($1)", &expired)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM jobs WHERE guild_id = ANY($1)", &expired)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM guild_locales WHERE guild_id = ANY($1)",
+            &expired
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM comic_subscriptions WHERE guild_id = ANY($1)",
+            &expired
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!("DELETE FROM comic_feeds WHERE guild_id = ANY($1)", &expired)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM guild_command_settings WHERE guild_id = ANY($1)",
+            &expired
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "
+            DELETE FROM cooldowns
+            WHERE bucket IN (SELECT 'guild:' || guild_id FROM UNNEST($1::bigint[]) AS guild_id)
+            ",
+            &expired
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+        Ok(expired
+            .into_iter()
+            .filter_map(|id| u64::try_from(id).ok())
+            .map(GuildId::new)
+            .collect())
+    })
+    .await
+}
+
+/// Periodically purges guilds whose grace period is over.
+pub async fn run_purge(
+    db: PgPool,
+    grace_period: Duration,
+    command_settings: Arc<GuildCommandSettings>,
+    guild_locales: Arc<GuildLocales>,
+) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match purge_expired(&db, grace_period).await {
+            Ok(guilds) if guilds.is_empty() => {}
+            Ok(guilds) => {
+                command_settings.forget(&guilds);
+                guild_locales.forget(&guilds);
+                info!(guilds = guilds.len(), "Purged data of removed guilds");
+            }
+            Err(err) => error!(error = %err, "Failed to purge removed guilds"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{balances, insert_user, GUILD, USER};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    async fn backdate_removal(db: &PgPool, guild_id: GuildId, days: i32) {
+        sqlx::query("UPDATE guild_removals SET removed_at = now() - make_interval(days => $1) WHERE guild_id = $2")
+            .bind(days)
+            .bind(i64::from(guild_id))
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    async fn user_count(db: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT count(*) FROM users")
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn keeps_data_during_the_grace_period(db: PgPool) {
+        insert_user(&db, USER, GUILD, 5, 0).await;
+        mark_removed(&db, GUILD).await.unwrap();
+        backdate_removal(&db, GUILD, 10).await;
+
+        assert!(purge_expired(&db, 30 * DAY).await.unwrap().is_empty());
+        assert_eq!(balances(&db, USER, GUILD).await, (5, 0));
+    }
+
+    #[sqlx::test]
+    async fn purges_data_after_the_grace_period(db: PgPool) {
+        let other_guild = GuildId::new(2);
+        insert_user(&db, USER, GUILD, 5, 0).await;
+        insert_user(&db, USER, other_guild, 5, 0).await;
+        mark_removed(&db, GUILD).await.unwrap();
+        backdate_removal(&db, GUILD, 31).await;
+
+        assert_eq!(purge_expired(&db, 30 * DAY).await.unwrap(), vec![GUILD]);
+        assert_eq!(user_count(&db).await, 1);
+        assert_eq!(balances(&db, USER, other_guild).await, (5, 0));
+        // The removal is cleared along with the data
+        assert!(!restore(&db, GUILD).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn purges_settings_and_guild_cooldowns(db: PgPool) {
+        for (guild_id, bucket) in [
+            (GUILD, format!("guild:{GUILD}")),
+            (GuildId::new(2), "guild:2".to_string()),
+        ] {
+            sqlx::query("INSERT INTO guild_command_settings (guild_id, command, enabled, allowed_channels, allowed_roles) VALUES ($1, 'xkcd', false, '{}', '{}')")
+                .bind(i64::from(guild_id))
+                .execute(&db)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO cooldowns (command, bucket, expires_at) VALUES ('work', $1, now() + interval '1 hour')")
+                .bind(bucket)
+                .execute(&db)
+                .await
+                .unwrap();
+        }
+        mark_removed(&db, GUILD).await.unwrap();
+        backdate_removal(&db, GUILD, 31).await;
+
+        assert_eq!(purge_expired(&db, 30 * DAY).await.unwrap(), vec![GUILD]);
+        let settings: Vec<i64> = sqlx::query_scalar("SELECT guild_id FROM guild_command_settings")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(settings, vec![2]);
+        let cooldowns: Vec<String> = sqlx::query_scalar("SELECT bucket FROM cooldowns")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(cooldowns, vec!["guild:2"]);
+    }
+
+    #[sqlx::test]
+    async fn legal_holds_block_the_purge(db: PgPool) {
+        insert_user(&db, USER, GUILD, 5, 0).await;
+        sqlx::query("INSERT INTO legal_holds (guild_id, reason) VALUES ($1, 'test')")
+            .bind(i64::from(GUILD))
+            .execute(&db)
+            .await
+            .unwrap();
+        mark_removed(&db, GUILD).await.unwrap();
+        backdate_removal(&db, GUILD, 31).await;
+
+        assert!(purge_expired(&db, 30 * DAY).await.unwrap().is_empty());
+        assert_eq!(balances(&db, USER, GUILD).await, (5, 0));
+    }
+
+    #[sqlx::test]
+    async fn restoring_cancels_the_purge(db: PgPool) {
+        insert_user(&db, USER, GUILD, 5, 0).await;
+        mark_removed(&db, GUILD).await.unwrap();
+        backdate_removal(&db, GUILD, 31).await;
+
+        assert!(restore(&db, GUILD).await.unwrap());
+        assert!(purge_expired(&db, 30 * DAY).await.unwrap().is_empty());
+        assert_eq!(balances(&db, USER, GUILD).await, (5, 0));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use poise::serenity_prelude::{ConnectionStage, ShardManager};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{error, info};
+
+use crate::metrics::METRICS;
+use crate::util::build_info;
+
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct HealthState {
+    pub db: PgPool,
+    pub shard_manager: Arc<ShardManager>,
+}
+
+#[derive(Serialize)]
+struct Readiness {
+    ready: bool,
+    database: bool,
+    shards: BTreeMap<u32, String>,
+}
+
+#[derive(Serialize)]
+struct BuildInfo {
+    version: &'static str,
+    build_timestamp: &'static str,
+    rustc: &'static str,
+    git_sha: &'static str,
+}
+
+/// Binds the health check and metrics server and serves it in the background.
+pub async fn spawn(address: SocketAddr, state: HealthState) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+        .route("/metrics", get(metrics))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    info!(%address, "Serving health checks");
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            error!(error = %err, "Health check server stopped");
+        }
+    });
+
+    Ok(())
+}
+
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Ready once every shard is connected to the gateway and the database answers a ping.
+async fn readyz(State(state): State<HealthState>) -> (StatusCode, Json<Readiness>) {
+    let shards: BTreeMap<u32, ConnectionStage> = state
+        .shard_manager
+        .runners
+        .lock()
+        .await
+        .iter()
+        .map(|(id, runner)| (id.0, runner.stage))
+        .collect();
+    let shards_connected = !shards.is_empty()
+        && shards
+            .values()
+            .all(|stage| *stage == ConnectionStage::Connected);
+
+    let database = matches!(
+        tokio::time::timeout(DB_PING_TIMEOUT, sqlx::query("SELECT 1").execute(&state.db)).await,
+        Ok(Ok(_))
+    );
+
+    let ready = shards_connected && database;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(Readiness {
+            ready,
+            database,
+            shards: shards
+                .into_iter()
+                .map(|(id, stage)| (id, stage.to_string()))
+                .collect(),
+        }),
+    )
+}
+
+async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo {
+        version: build_info::VERSION,
+        build_timestamp: build_info::BUILD_TIMESTAMP,
+        rustc: build_info::RUSTC_SEMVER,
+        git_sha: build_info::GIT_SHA,
+    })
+}
+
+async fn metrics(State(state): State<HealthState>) -> String {
+    METRICS.render(&state.db, &state.shard_manager).await
+}
@@ -0,0 +1,373 @@
+//! Translations of command metadata and replies, loaded from the Fluent files in `locales/`.
+//!
+//! Replies use the guild's language if an admin has set one with `/settings language`, and
+//! the member's Discord language otherwise. Anything missing falls back to English.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource};
+use poise::serenity_prelude::GuildId;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::metrics::time_query;
+use crate::{Context, Data, Error};
+
+/// Every shipped locale, by Discord locale code. The first one is the fallback.
+const LOCALES: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+static BUNDLES: LazyLock<Vec<FluentBundle<FluentResource>>> = LazyLock::new(|| {
+    LOCALES
+        .iter()
+        .map(|(code, source)| {
+            let resource = FluentResource::try_new((*source).to_string())
+                .unwrap_or_else(|(_, errors)| panic!("invalid {code}.ftl: {errors:?}"));
+            let mut bundle = FluentBundle::new_concurrent(vec![code
+                .parse()
+                .unwrap_or_else(|err| panic!("invalid locale `{code}`: {err}"))]);
+            // Unicode isolation marks show up as stray characters in Discord
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|errors| panic!("duplicate keys in {code}.ftl: {errors:?}"));
+            bundle
+        })
+        .collect()
+});
+
+/// Looks up a translation, optionally with arguments:
+/// - `tr!(locale, "key")`
+/// - `tr!(locale, "key", name = value, other = value)`
+macro_rules! tr {
+    ($locale:expr, $id:expr $(, $arg:ident = $value:expr)* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut args = fluent::FluentArgs::new();
+        $( args.set(stringify!($arg), $value); )*
+        $locale.get($id, &args)
+    }};
+}
+pub(crate) use tr;
+
+/// A shipped locale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale(usize);
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::ENGLISH
+    }
+}
+
+impl Locale {
+    /// The fallback for anything missing from other locales.
+    pub const ENGLISH: Self = Self(0);
+
+    /// Finds the shipped locale for a Discord locale code, matching on the language alone
+    /// (so `en-GB` gets `en-US`) if there's no exact match.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let language = |code: &str| code.split('-').next().unwrap_or_default().to_string();
+        LOCALES
+            .iter()
+            .position(|(shipped, _)| *shipped == code)
+            .or_else(|| {
+                LOCALES
+                    .iter()
+                    .position(|(shipped, _)| language(shipped) == language(code))
+            })
+            .map(Self)
+    }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..LOCALES.len()).map(Self)
+    }
+
+    pub fn code(self) -> &'static str {
+        LOCALES[self.0].0
+    }
+
+    /// The locale's name for itself, like "Deutsch".
+    pub fn name(self) -> String {
+        self.get("language-name", &FluentArgs::new())
+    }
+
+    pub fn get(self, id: &str, args: &FluentArgs<'_>) -> String {
+        format(self.0, id, None, Some(args))
+            .or_else(|| format(0, id, None, Some(args)))
+            .unwrap_or_else(|| {
+                warn!(id, "Missing translation");
+                id.to_string()
+            })
+    }
+}
+
+fn format(
+    bundle: usize,
+    id: &str,
+    attribute: Option<&str>,
+    args: Option<&FluentArgs<'_>>,
+) -> Option<String> {
+    let bundle = &BUNDLES[bundle];
+    let message = bundle.get_message(id)?;
+    let pattern = match attribute {
+        Some(attribute) => message.get_attribute(attribute)?.value(),
+        None => message.value()?,
+    };
+    Some(
+        bundle
+            .format_pattern(pattern, args, &mut Vec::new())
+            .into_owned(),
+    )
+}
+
+/// Languages admins have chosen for their guilds, overriding members' own.
+#[derive(Default)]
+pub struct GuildLocales {
+    locales: RwLock<HashMap<GuildId, Locale>>,
+}
+
+impl GuildLocales {
+    pub async fn load(db: &PgPool) -> sqlx::Result<Self> {
+        let records = time_query(
+            "load_guild_locales",
+            sqlx::query!("SELECT guild_id, locale FROM guild_locales").fetch_all(db),
+        )
+        .await?;
+
+        let locales = records
+            .into_iter()
+            .filter_map(|record| {
+                let guild_id = GuildId::new(u64::try_from(record.guild_id).ok()?);
+                Some((guild_id, Locale::from_code(&record.locale)?))
+            })
+            .collect();
+        Ok(Self {
+            locales: RwLock::new(locales),
+        })
+    }
+
+    pub fn get(&self, guild_id: GuildId) -> Option<Locale> {
+        self.locales.read().unwrap().get(&guild_id).copied()
+    }
+
+    /// Sets the guild's language, or clears it if `locale` is `None`.
+    pub async fn set(
+        &self,
+        db: &PgPool,
+        guild_id: GuildId,
+        locale: Option<Locale>,
+    ) -> sqlx::Result<()> {
+        if let Some(locale) = locale {
+            time_query(
+                "set_guild_locale",
+                sqlx::query!(
+                    "
+                        INSERT INTO guild_locales (guild_id, locale) VALUES ($1, $2)
+                        ON CONFLICT (guild_id) DO UPDATE SET locale = EXCLUDED.locale
+                        ",
+                    i64::from(guild_id),
+                    locale.code()
+                )
+                .execute(db),
+            )
+            .await?;
+            self.locales.write().unwrap().insert(guild_id, locale);
+        } else {
+            time_query(
+                "clear_guild_locale",
+                sqlx::query!(
+                    "DELETE FROM guild_locales WHERE guild_id = $1",
+                    i64::from(guild_id)
+                )
+                .execute(db),
+            )
+            .await?;
+            self.locales.write().unwrap().remove(&guild_id);
+        }
+
+        Ok(())
+    }
+
+    /// Drops the languages of guilds whose data was purged.
+    pub fn forget(&self, guild_ids: &[GuildId]) {
+        let mut locales = self.locales.write().unwrap();
+        for guild_id in guild_ids {
+            locales.remove(guild_id);
+        }
+    }
+}
+
+/// The locale to reply to an invocation in.
+pub fn locale(ctx: Context<'_>) -> Locale {
+    resolve(ctx.data(), ctx.guild_id(), ctx.locale())
+}
+
+/// The locale to reply in, given where and by whom Avion was used. Interactions outside
+/// commands, like button presses, don't have a `Context` to go by.
+pub fn resolve(data: &Data, guild_id: Option<GuildId>, user_locale: Option<&str>) -> Locale {
+    guild_id
+        .and_then(|guild_id| data.guild_locales.get(guild_id))
+        .or_else(|| user_locale.and_then(Locale::from_code))
+        .unwrap_or_default()
+}
+
+/// The Fluent message ID for a command, which is its qualified name with dashes. Qualified
+/// names are only filled in once the framework is built, so this goes by the parent's ID.
+fn command_id(parent: Option<&str>, command: &poise::Command<Data, Error>) -> String {
+    match parent {
+        Some(parent) => format!("{parent}-{}", command.name),
+        None => command.name.clone(),
+    }
+}
+
+/// Sets every command's name and description, and its parameters', from the locale files.
+pub fn apply_translations(commands: &mut [poise::Command<Data, Error>]) {
+    translate(commands, None);
+}
+
+fn translate(commands: &mut [poise::Command<Data, Error>], parent: Option<&str>) {
+    for command in commands {
+        let id = command_id(parent, command);
+        translate(&mut command.subcommands, Some(&id));
+
+        for locale in Locale::all().skip(1) {
+            let code = locale.code().to_string();
+            let get = |attribute: Option<&str>| format(locale.0, &id, attribute, None);
+            if let Some(name) = get(None) {
+                command.name_localizations.insert(code.clone(), name);
+            }
+            if let Some(description) = get(Some("description")) {
+                command
+                    .description_localizations
+                    .insert(code.clone(), description);
+            }
+            for parameter in &mut command.parameters {
+                if let Some(name) = get(Some(&parameter.name)) {
+                    parameter.name_localizations.insert(code.clone(), name);
+                }
+                if let Some(description) = get(Some(&format!("{}-description", parameter.name))) {
+                    parameter
+                        .description_localizations
+                        .insert(code.clone(), description);
+                }
+            }
+        }
+
+        // English descriptions come from the locale file too, so they're all in one place
+        if let Some(description) = format(0, &id, Some("description"), None) {
+            command.description = Some(description);
+        }
+        for parameter in &mut command.parameters {
+            let attribute = format!("{}-description", parameter.name);
+            if let Some(description) = format(0, &id, Some(&attribute), None) {
+                parameter.description = Some(description);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent_syntax::ast::{Entry, Message};
+
+    use super::*;
+    use crate::config::FeaturesConfig;
+    use crate::test_utils::GUILD;
+
+    /// The message IDs and attributes in a locale file.
+    fn keys(source: &str) -> Vec<String> {
+        let resource = FluentResource::try_new(source.to_string()).unwrap();
+        let mut keys = Vec::new();
+        for entry in resource.entries() {
+            if let Entry::Message(Message { id, attributes, .. }) = entry {
+                keys.push(id.name.to_string());
+                keys.extend(
+                    attributes
+                        .iter()
+                        .map(|attribute| format!("{}.{}", id.name, attribute.id.name)),
+                );
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn every_locale_has_every_key() {
+        let (_, english) = LOCALES[0];
+        let english = keys(english);
+        for (code, source) in &LOCALES[1..] {
+            let translated = keys(source);
+            for key in &english {
+                assert!(
+                    translated.contains(key),
+                    "`{key}` is missing from {code}.ftl"
+                );
+            }
+            for key in &translated {
+                assert!(
+                    english.contains(key),
+                    "`{key}` in {code}.ftl isn't in English"
+                );
+            }
+        }
+    }
+
+    fn check_commands(commands: &[poise::Command<Data, Error>], parent: Option<&str>) {
+        for command in commands {
+            let id = command_id(parent, command);
+            check_commands(&command.subcommands, Some(&id));
+            assert!(
+                format(0, &id, Some("description"), None).is_some(),
+                "`{id}` has no description"
+            );
+            for locale in Locale::all().skip(1) {
+                assert!(
+                    format(locale.0, &id, None, None).is_some(),
+                    "`{id}` has no name in {}",
+                    locale.code()
+                );
+            }
+            for parameter in &command.parameters {
+                let attribute = format!("{}-description", parameter.name);
+                assert!(
+                    format(0, &id, Some(&attribute), None).is_some(),
+                    "`{id}.{attribute}` is missing"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_command_is_translated() {
+        let commands = crate::enabled_commands(&FeaturesConfig {
+            xkcd: true,
+            economy: true,
+            feeds: true,
+            member_counts: true,
+        });
+        check_commands(&commands, None);
+    }
+
+    #[test]
+    fn matches_discord_locales() {
+        assert_eq!(Locale::from_code("en-US"), Some(Locale::default()));
+        assert_eq!(Locale::from_code("en-GB"), Some(Locale::default()));
+        assert_eq!(Locale::from_code("de").map(Locale::code), Some("de"));
+        assert_eq!(Locale::from_code("ja"), None);
+    }
+
+    #[sqlx::test]
+    async fn forgets_purged_guilds(db: PgPool) {
+        let german = Locale::from_code("de");
+        let locales = GuildLocales::load(&db).await.unwrap();
+        locales.set(&db, GUILD, german).await.unwrap();
+        assert_eq!(locales.get(GUILD), german);
+
+        locales.forget(&[GUILD]);
+        assert_eq!(locales.get(GUILD), None);
+    }
+}
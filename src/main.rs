@@ -1,76 +1,98 @@
-use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
 
 use color_eyre::Result;
-use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
-use poise::serenity_prelude::{ActivityData, ClientBuilder, FullEvent, GatewayIntents, GuildId};
+use poise::serenity_prelude::{
+    ActivityData, ClientBuilder, FullEvent, GatewayIntents, Http, Interaction, Timestamp,
+};
 use poise::FrameworkContext;
 
-use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
 mod commands;
 #[allow(clippy::wildcard_imports)]
 use commands::*;
+mod comics;
+mod command_settings;
+mod config;
+mod cooldowns;
+use config::{Config, FeaturesConfig, Sharding};
+use cooldowns::Cooldowns;
+mod economy;
+use economy::{EconomyService, PgStore};
 mod embeds;
+mod guild_cleanup;
+mod http;
+mod i18n;
+mod metrics;
+mod privacy;
+mod shutdown;
+use shutdown::Shutdown;
+#[cfg(test)]
+mod test_utils;
 mod util;
+mod xkcd;
 //use libc::malloc_trim; malloc_trim(0) trick for performance
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 
+/// How long to wait for in-flight commands to finish when shutting down.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(20);
+
 // User data, which is stored and accessible in all command invocations
 struct Data {
     pub db: PgPool,
-    pub client: reqwest::Client,
-}
-
-#[derive(Serialize, Deserialize)]
-struct Config {
-    discord_token: String,
-    #[serde(rename = "database_url")]
-    db_url: String,
-    #[serde(rename = "discord_testing_guild_id")]
-    testing_guild_id: Option<String>,
-    sentry_dsn: Option<String>,
+    pub xkcd: Arc<xkcd::XkcdClient>,
+    pub feeds: Arc<comics::feeds::Feeds>,
+    pub shutdown: Shutdown,
+    pub economy: EconomyService<PgStore>,
+    pub cooldowns: Cooldowns,
+    pub guild_locales: Arc<i18n::GuildLocales>,
+    pub command_settings: Arc<command_settings::GuildCommandSettings>,
+    /// Whether the Server Members intent is requested, so role members can be counted.
+    pub member_counts: bool,
 }
 
 async fn bot_main(config: Config) -> Result<()> {
-    let intents = GatewayIntents::GUILD_INTEGRATIONS | GatewayIntents::GUILDS;
+    let mut intents = GatewayIntents::GUILD_INTEGRATIONS | GatewayIntents::GUILDS;
+    if config.features.member_counts {
+        intents |= GatewayIntents::GUILD_MEMBERS;
+    }
 
-    let commands = vec![
-        user_info(),
-        about(),
-        avatar(),
-        balance(),
-        give(),
-        register(),
-        xkcd(),
-    ];
+    let commands = commands(&config);
 
-    for command in &commands {
-        assert!(
-            !(command.description.is_none() && command.subcommands.is_empty()),
-            "Command `{}` has no description",
-            command.name
-        );
-    }
+    debug!("Creating PgPool...");
+    let db = util::db::connect(&config.database).await?;
+    tokio::spawn(util::db::log_pool_stats(db.clone()));
+
+    let http_client = reqwest::Client::builder()
+        .timeout(config.xkcd.timeout)
+        .build()?;
+    let xkcd = Arc::new(xkcd::XkcdClient::new(http_client, db.clone(), &config.xkcd));
+    let feeds = Arc::new(comics::feeds::Feeds::new(config.xkcd.timeout, db.clone())?);
+    let shutdown = Shutdown::new();
+    let data = load_data(&config, &db, xkcd.clone(), feeds.clone(), shutdown.clone()).await?;
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             commands,
             event_handler: |framework, event| Box::pin(event_handler(framework, event)),
+            pre_command: |ctx| Box::pin(pre_command(ctx)),
+            post_command: |ctx| Box::pin(metrics::post_command(ctx)),
+            on_error: |error| Box::pin(metrics::on_error(error)),
+            command_check: Some(|ctx| Box::pin(command_check(ctx))),
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
                 debug!("Registering slash commands...");
-                if let Some(testing_guild_id) = &config.testing_guild_id {
-                    warn!(testing_guild_id, "Registering commands in the test guild");
-                    let guild_id = GuildId::from_str(testing_guild_id.as_str())?;
+                if let Some(guild_id) = config.testing_guild_id {
+                    warn!(%guild_id, "Registering commands in the test guild");
                     poise::builtins::register_in_guild(
                         ctx,
                         &framework.options().commands,
@@ -81,41 +103,305 @@ async fn bot_main(config: Config) -> Result<()> {
                     poise::builtins::register_globally(ctx, &framework.options().commands).await?;
                 }
 
-                debug!("Creating PgPool...");
-                let pool = PgPoolOptions::new()
-                    .max_connections(5)
-                    .connect(&config.db_url)
-                    .await?;
-
-                Ok(Data {
-                    db: pool,
-                    client: reqwest::Client::new(),
-                })
+                Ok(data)
             })
         })
         .build();
 
-    let client = ClientBuilder::new(&config.discord_token, intents)
+    let mut client = ClientBuilder::new(&config.discord_token, intents)
         .activity(ActivityData::watching("over your server"))
         .framework(framework)
-        .await;
-    client?.start().await?;
+        .await?;
+    spawn_comic_tasks(&config.features, xkcd, feeds, client.http.clone(), &db);
+
+    if let Some(address) = config.http.address {
+        http::spawn(
+            address,
+            http::HealthState {
+                db: db.clone(),
+                shard_manager: client.shard_manager.clone(),
+            },
+        )
+        .await?;
+    }
+
+    let shard_manager = client.shard_manager.clone();
+    let graceful_shutdown = async move {
+        shutdown::wait_for_signal().await;
+        info!("Shutting down, waiting for in-flight commands to finish...");
+        shutdown.trigger();
+        shutdown.drain(SHUTDOWN_GRACE_PERIOD).await;
+        shard_manager.shutdown_all().await;
+    };
+
+    // Keep the client running while draining, so in-flight commands can still reply
+    let start = async {
+        match config.sharding {
+            Sharding::Auto => client.start_autosharded().await,
+            Sharding::Range { shards, total } => {
+                info!(?shards, total, "Starting shard range");
+                client.start_shard_range(shards, total).await
+            }
+        }
+    };
+    tokio::select! {
+        result = start => result?,
+        () = graceful_shutdown => {}
+    }
+
+    debug!("Closing PgPool...");
+    db.close().await;
+    info!("Shut down cleanly");
 
     Ok(())
 }
 
-#[allow(clippy::unused_async)]
+/// Keeps the xkcd search index up to date, and posts new comics to subscribed channels.
+fn spawn_comic_tasks(
+    features: &FeaturesConfig,
+    xkcd: Arc<xkcd::XkcdClient>,
+    feeds: Arc<comics::feeds::Feeds>,
+    http: Arc<Http>,
+    db: &PgPool,
+) {
+    if features.xkcd {
+        tokio::spawn(xkcd::search::run_sync(xkcd.clone(), db.clone()));
+    }
+    if features.feeds {
+        tokio::spawn(comics::feeds::run_refresh(feeds.clone()));
+    }
+    if features.xkcd || features.feeds {
+        tokio::spawn(comics::subscriptions::run_poller(
+            http,
+            xkcd,
+            feeds,
+            db.clone(),
+            features.clone(),
+        ));
+    }
+}
+
+async fn pre_command(ctx: Context<'_>) {
+    ctx.set_invocation_data(ctx.data().shutdown.start_invocation())
+        .await;
+    metrics::record_invocation(ctx);
+}
+
+/// Loads the state shared by every command, and starts purging removed guilds' data.
+async fn load_data(
+    config: &Config,
+    db: &PgPool,
+    xkcd: Arc<xkcd::XkcdClient>,
+    feeds: Arc<comics::feeds::Feeds>,
+    shutdown: Shutdown,
+) -> sqlx::Result<Data> {
+    let command_settings = Arc::new(command_settings::GuildCommandSettings::load(db).await?);
+    let guild_locales = Arc::new(i18n::GuildLocales::load(db).await?);
+    tokio::spawn(guild_cleanup::run_purge(
+        db.clone(),
+        config.cleanup.grace_period,
+        command_settings.clone(),
+        guild_locales.clone(),
+    ));
+
+    Ok(Data {
+        db: db.clone(),
+        xkcd,
+        feeds,
+        shutdown,
+        economy: EconomyService::new(PgStore::new(db.clone())),
+        cooldowns: Cooldowns::load(config.cooldowns.clone(), db.clone()).await?,
+        guild_locales,
+        command_settings,
+        member_counts: config.features.member_counts,
+    })
+}
+
+/// Builds the enabled commands, with their translations.
+fn enabled_commands(features: &FeaturesConfig) -> Vec<poise::Command<Data, Error>> {
+    let mut commands = vec![
+        user_info(),
+        user_info_menu(),
+        server_info(),
+        role_info(),
+        channel_info(),
+        about(),
+        avatar(),
+        avatar_menu(),
+        avatar_message_menu(),
+        banner(),
+        privacy(),
+        settings(),
+    ];
+    if features.economy {
+        commands.extend([balance(), ecoadmin(), give(), register()]);
+    }
+    if features.xkcd {
+        commands.push(xkcd());
+    }
+    if features.feeds {
+        commands.push(feed());
+    }
+
+    i18n::apply_translations(&mut commands);
+    // The framework fills these in too, but cooldowns are matched against them before that
+    poise::set_qualified_names(&mut commands);
+    add_cooldown_checks(&mut commands);
+    commands
+}
+
+/// Builds the enabled commands, checking they're fit to register.
+fn commands(config: &Config) -> Vec<poise::Command<Data, Error>> {
+    let commands = enabled_commands(&config.features);
+    for command in &commands {
+        assert!(
+            !(command.description.is_none() && command.subcommands.is_empty()),
+            "Command `{}` has no description",
+            command.name
+        );
+    }
+
+    for command in config.cooldowns.keys() {
+        if !commands.iter().any(|c| has_command(c, command)) {
+            warn!(command, "Cooldown configured for an unknown command");
+        }
+    }
+
+    commands
+}
+
+/// Whether `command` or one of its subcommands has the qualified name `name`.
+fn has_command(command: &poise::Command<Data, Error>, name: &str) -> bool {
+    command.qualified_name == name
+        || command
+            .subcommands
+            .iter()
+            .any(|subcommand| has_command(subcommand, name))
+}
+
+/// Turns away new commands once a shutdown has started, and commands the guild has
+/// restricted. Poise runs this for every parent of a subcommand too, so it shouldn't count
+/// uses.
+async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    // Autocomplete can't be replied to, and restricted commands fail once they're run anyway
+    if is_autocomplete(ctx) {
+        return Ok(true);
+    }
+
+    let locale = i18n::locale(ctx);
+    let embed = if ctx.data().shutdown.is_triggered() {
+        embeds::bot_restarting(locale)
+    } else if let Err(denial) = command_settings::check(ctx).await? {
+        denial.into_embed(locale)
+    } else {
+        return Ok(true);
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(false)
+}
+
+/// Turns away commands on cooldown. Only leaf commands run it, so a subcommand's use is only
+/// counted once.
+async fn cooldown_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_autocomplete(ctx) {
+        return Ok(true);
+    }
+
+    let Some(until) = ctx
+        .data()
+        .cooldowns
+        .hit(
+            &ctx.command().qualified_name,
+            ctx.guild_id(),
+            ctx.author().id,
+        )
+        .await?
+    else {
+        return Ok(true);
+    };
+
+    let secs = until
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + 1;
+    let embed = embeds::on_cooldown(
+        i18n::locale(ctx),
+        Timestamp::from_unix_timestamp(i64::try_from(secs)?)?,
+    );
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(false)
+}
+
+fn is_autocomplete(ctx: Context<'_>) -> bool {
+    matches!(
+        ctx,
+        poise::Context::Application(ctx)
+            if ctx.interaction_type == poise::CommandInteractionType::Autocomplete
+    )
+}
+
+/// Adds [`cooldown_check`] to every command that can be run, leaving out the parents of
+/// subcommands.
+fn add_cooldown_checks(commands: &mut [poise::Command<Data, Error>]) {
+    for command in commands {
+        if command.subcommands.is_empty() {
+            command.checks.push(|ctx| Box::pin(cooldown_check(ctx)));
+        } else {
+            add_cooldown_checks(&mut command.subcommands);
+        }
+    }
+}
+
 async fn event_handler(
-    _framework: FrameworkContext<'_, Data, Error>,
+    framework: FrameworkContext<'_, Data, Error>,
     event: &FullEvent,
 ) -> Result<(), Error> {
-    if let FullEvent::Ready { data_about_bot, .. } = event {
-        info!(
-            "Ready! Logged in as {}#{}",
-            data_about_bot.user.name,
-            // Should never be None, as bots still use the "Name#0000" format instead of usernames
-            data_about_bot.user.discriminator.unwrap()
-        );
+    match event {
+        FullEvent::Ready { data_about_bot, .. } => {
+            let shard = data_about_bot.shard.map(|shard| shard.id.0);
+            info!(
+                ?shard,
+                "Ready! Logged in as {}#{}",
+                data_about_bot.user.name,
+                // Should never be None, as bots still use the "Name#0000" format instead of usernames
+                data_about_bot.user.discriminator.unwrap()
+            );
+        }
+        FullEvent::ShardStageUpdate { event } => {
+            info!(
+                shard = event.shard_id.0,
+                old = %event.old,
+                new = %event.new,
+                "Shard connection stage changed"
+            );
+        }
+        // Sent for every guild on startup too, so only log when a removal was cancelled
+        FullEvent::GuildCreate { guild, .. } => {
+            let restored = guild_cleanup::restore(&framework.user_data().db, guild.id).await?;
+            if restored {
+                info!(guild_id = %guild.id, "Re-added to guild, cancelled data deletion");
+            }
+        }
+        // Unavailable guilds are having an outage, not removing Avion
+        FullEvent::GuildDelete { incomplete, .. } if !incomplete.unavailable => {
+            guild_cleanup::mark_removed(&framework.user_data().db, incomplete.id).await?;
+            info!(guild_id = %incomplete.id, "Removed from guild, marked data for deletion");
+        }
+        FullEvent::InteractionCreate {
+            interaction: Interaction::Component(interaction),
+        } => {
+            comics::navigation::handle(
+                framework.serenity_context,
+                framework.user_data(),
+                interaction,
+            )
+            .await?;
+        }
+        _ => {}
     }
 
     Ok(())
@@ -124,8 +410,8 @@ async fn event_handler(
 fn main() -> Result<()> {
     color_eyre::install()?;
     let _ = dotenvy::dotenv();
-    let config = envy::from_env::<Config>()?;
-    let mut _guard = None;
+    let config = Config::load()?;
+    let mut sentry_guard = None;
 
     match &config.sentry_dsn {
         Some(dsn) => {
@@ -133,7 +419,7 @@ fn main() -> Result<()> {
             let options = sentry::ClientOptions::default();
             let options = sentry::apply_defaults(options);
 
-            _guard = Some(sentry::init((dsn.clone(), options)));
+            sentry_guard = Some(sentry::init((dsn.clone(), options)));
         }
         _ => {
             // We have to use eprintln here because we haven't initialized the logger yet
@@ -152,5 +438,36 @@ fn main() -> Result<()> {
         .build()?
         .block_on(bot_main(config))?;
 
+    // Dropping the guard flushes any events still queued for Sentry
+    drop(sentry_guard);
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subcommands_are_cooldown_checked_once() {
+        let commands = enabled_commands(&FeaturesConfig {
+            xkcd: true,
+            economy: true,
+            feeds: true,
+            member_counts: true,
+        });
+
+        // Poise runs the checks of the command and of each of its parents
+        let mut parents = Vec::new();
+        let (command, _, _) =
+            poise::find_command(&commands, "xkcd random", false, &mut parents).unwrap();
+        assert_eq!(command.qualified_name, "xkcd random");
+        assert_eq!(parents.len(), 1);
+        let checks: usize = parents
+            .iter()
+            .chain([&command])
+            .map(|command| command.checks.len())
+            .sum();
+        assert_eq!(checks, 1);
+    }
+}
@@ -0,0 +1,235 @@
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+
+use poise::serenity_prelude::ShardManager;
+use poise::FrameworkError;
+use prometheus::core::Collector;
+use prometheus::{
+    GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::shutdown::Invocation;
+use crate::{Context, Data, Error};
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    commands: IntCounterVec,
+    command_duration: HistogramVec,
+    errors: IntCounterVec,
+    db_query_duration: HistogramVec,
+    gateway_latency: GaugeVec,
+    xkcd_request_duration: HistogramVec,
+    coins_in_circulation: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("avion".to_string()), None).expect("metric prefix is valid");
+
+        let commands = IntCounterVec::new(
+            Opts::new("commands_total", "Command invocations"),
+            &["command"],
+        )
+        .unwrap();
+        let command_duration = HistogramVec::new(
+            HistogramOpts::new("command_duration_seconds", "Time taken to run a command"),
+            &["command"],
+        )
+        .unwrap();
+        let errors = IntCounterVec::new(
+            Opts::new("errors_total", "Framework errors, by kind"),
+            &["kind"],
+        )
+        .unwrap();
+        let db_query_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "db_query_duration_seconds",
+                "Time taken by database queries",
+            ),
+            &["query"],
+        )
+        .unwrap();
+        let gateway_latency = GaugeVec::new(
+            Opts::new("gateway_latency_seconds", "Gateway heartbeat latency"),
+            &["shard"],
+        )
+        .unwrap();
+        let xkcd_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "xkcd_request_duration_seconds",
+                "Time taken by requests to xkcd.com",
+            ),
+            &["outcome"],
+        )
+        .unwrap();
+        let coins_in_circulation = IntGaugeVec::new(
+            Opts::new(
+                "coins_in_circulation",
+                "Coins held in wallets and banks, by guild",
+            ),
+            &["guild"],
+        )
+        .unwrap();
+
+        for collector in [
+            Box::new(commands.clone()) as Box<dyn Collector>,
+            Box::new(command_duration.clone()),
+            Box::new(errors.clone()),
+            Box::new(db_query_duration.clone()),
+            Box::new(gateway_latency.clone()),
+            Box::new(xkcd_request_duration.clone()),
+            Box::new(coins_in_circulation.clone()),
+        ] {
+            registry.register(collector).unwrap();
+        }
+
+        Self {
+            registry,
+            commands,
+            command_duration,
+            errors,
+            db_query_duration,
+            gateway_latency,
+            xkcd_request_duration,
+            coins_in_circulation,
+        }
+    }
+
+    /// Refreshes the gauges that are sampled rather than recorded, then encodes every metric
+    /// in the Prometheus text format.
+    pub async fn render(&self, db: &PgPool, shard_manager: &Arc<ShardManager>) -> String {
+        self.gateway_latency.reset();
+        for (id, runner) in shard_manager.runners.lock().await.iter() {
+            if let Some(latency) = runner.latency {
+                self.gateway_latency
+                    .with_label_values(&[&id.to_string()])
+                    .set(latency.as_secs_f64());
+            }
+        }
+
+        match coins_in_circulation(db).await {
+            Ok(totals) => {
+                self.coins_in_circulation.reset();
+                for (guild_id, total) in totals {
+                    self.coins_in_circulation
+                        .with_label_values(&[&guild_id.to_string()])
+                        .set(total);
+                }
+            }
+            Err(err) => error!(error = %err, "Failed to total coins in circulation"),
+        }
+
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_default()
+    }
+
+    pub fn observe_xkcd_request(&self, outcome: &str, start: Instant) {
+        self.xkcd_request_duration
+            .with_label_values(&[outcome])
+            .observe(start.elapsed().as_secs_f64());
+    }
+}
+
+/// Every guild's coins across wallets and banks, as `(guild_id, total)`.
+async fn coins_in_circulation(db: &PgPool) -> sqlx::Result<Vec<(i64, i64)>> {
+    // Balances are summed as bigints, as a user's wallet and bank can add up to more than an
+    // int holds
+    time_query(
+        "coins_in_circulation",
+        sqlx::query_as(
+            "SELECT guild_id, SUM(wallet_balance::bigint + bank_balance::bigint)::bigint FROM users GROUP BY guild_id",
+        )
+        .fetch_all(db),
+    )
+    .await
+}
+
+/// Runs a database query, recording how long it took under `name`.
+pub async fn time_query<F: Future>(name: &str, query: F) -> F::Output {
+    let timer = METRICS
+        .db_query_duration
+        .with_label_values(&[name])
+        .start_timer();
+    let output = query.await;
+    timer.observe_duration();
+    output
+}
+
+pub fn record_invocation(ctx: Context<'_>) {
+    METRICS
+        .commands
+        .with_label_values(&[&ctx.command().qualified_name])
+        .inc();
+}
+
+pub async fn post_command(ctx: Context<'_>) {
+    observe_command_duration(ctx).await;
+}
+
+async fn observe_command_duration(ctx: Context<'_>) {
+    if let Some(invocation) = ctx.invocation_data::<Invocation>().await {
+        METRICS
+            .command_duration
+            .with_label_values(&[&ctx.command().qualified_name])
+            .observe(invocation.started_at.elapsed().as_secs_f64());
+    }
+}
+
+/// Counts the error by kind, then hands it to poise's default handler.
+pub async fn on_error(error: FrameworkError<'_, Data, Error>) {
+    METRICS
+        .errors
+        .with_label_values(&[error_kind(&error)])
+        .inc();
+    match &error {
+        FrameworkError::Command { ctx, .. } => observe_command_duration(*ctx).await,
+        // Checks that reject without an error have already told the user why.
+        FrameworkError::CommandCheckFailed { error: None, .. } => return,
+        _ => {}
+    }
+
+    if let Err(err) = poise::builtins::on_error(error).await {
+        error!(error = %err, "Failed to handle framework error");
+    }
+}
+
+fn error_kind(error: &FrameworkError<'_, Data, Error>) -> &'static str {
+    match error {
+        FrameworkError::Setup { .. } => "setup",
+        FrameworkError::EventHandler { .. } => "event_handler",
+        FrameworkError::Command { .. } => "command",
+        FrameworkError::CommandPanic { .. } => "command_panic",
+        FrameworkError::ArgumentParse { .. } => "argument_parse",
+        FrameworkError::CooldownHit { .. } => "cooldown_hit",
+        FrameworkError::MissingBotPermissions { .. } => "missing_bot_permissions",
+        FrameworkError::MissingUserPermissions { .. } => "missing_user_permissions",
+        FrameworkError::CommandCheckFailed { .. } => "command_check_failed",
+        FrameworkError::GuildOnly { .. } => "guild_only",
+        FrameworkError::UnknownInteraction { .. } => "unknown_interaction",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{insert_user, GUILD, OTHER_USER, USER};
+
+    #[sqlx::test]
+    async fn totals_balances_bigger_than_an_int(db: PgPool) {
+        insert_user(&db, USER, GUILD, i32::MAX, i32::MAX).await;
+        insert_user(&db, OTHER_USER, GUILD, i32::MAX - 1, 1).await;
+
+        assert_eq!(
+            coins_in_circulation(&db).await.unwrap(),
+            vec![(i64::from(GUILD), 3 * i64::from(i32::MAX))]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Exporting and deleting everything Avion stores about a user.
+//!
+//! Every table holding personal data is listed in [`PERSONAL_DATA`], and a test fails if a
+//! table with a `user_id` or `bucket` column is missing from it, so new tables can't be
+//! forgotten.
+
+use poise::serenity_prelude::{GuildId, UserId};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+
+use crate::metrics::time_query;
+
+/// A table with rows keyed to a user.
+pub struct PersonalTable {
+    pub name: &'static str,
+    pub user_column: &'static str,
+    /// The guild the rows belong to, if any, so legal holds can be respected.
+    pub guild_column: Option<&'static str>,
+    pub user_ids: IdFormat,
+}
+
+/// How a table stores user IDs.
+pub enum IdFormat {
+    Bigint,
+    /// As text, like archived tables from before IDs were bigints.
+    Text,
+    /// As text after a prefix, like the `user:` cooldown buckets.
+    Prefixed(&'static str),
+}
+
+impl PersonalTable {
+    /// A condition matching the user's rows, with their ID bound as `$1`. The ID is cast
+    /// rather than the column, so indexes on it can still be used.
+    fn matches_user(&self) -> String {
+        let user = self.user_column;
+        match self.user_ids {
+            IdFormat::Bigint => format!("{user} = $1"),
+            IdFormat::Text => format!("{user} = $1::text"),
+            IdFormat::Prefixed(prefix) => format!("{user} = '{prefix}' || $1::text"),
+        }
+    }
+}
+
+pub const PERSONAL_DATA: &[PersonalTable] = &[
+    PersonalTable {
+        name: "users",
+        user_column: "user_id",
+        guild_column: Some("guild_id"),
+        user_ids: IdFormat::Bigint,
+    },
+    // Balances from before they were per guild, set aside by the bigint migration
+    PersonalTable {
+        name: "users_without_guild",
+        user_column: "user_id",
+        guild_column: None,
+        user_ids: IdFormat::Text,
+    },
+    // Persisted cooldowns of a single user
+    PersonalTable {
+        name: "cooldowns",
+        user_column: "bucket",
+        guild_column: None,
+        user_ids: IdFormat::Prefixed("user:"),
+    },
+];
+
+/// What a deletion removed, and what it had to keep.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Deletion {
+    pub rows: u64,
+    /// Guilds under a legal hold, whose rows were kept.
+    pub held_guilds: Vec<GuildId>,
+}
+
+// Table and column names only ever come from `PERSONAL_DATA`, never from users, so building
+// queries from them is safe.
+
+/// Collects every row keyed to the user, grouped by table.
+pub async fn export(db: &PgPool, user_id: UserId) -> sqlx::Result<Value> {
+    let mut tables = Map::new();
+    for table in PERSONAL_DATA {
+        let rows: Vec<Value> = time_query(
+            "privacy_export",
+            sqlx::query_scalar(&format!(
+                "SELECT to_jsonb(t) FROM {name} t WHERE {matches}",
+                name = table.name,
+                matches = table.matches_user(),
+            ))
+            .bind(i64::from(user_id))
+            .fetch_all(db),
+        )
+        .await?;
+        tables.insert(table.name.to_string(), Value::Array(rows));
+    }
+
+    Ok(serde_json::json!({
+        "user_id": user_id.to_string(),
+        "tables": tables,
+    }))
+}
+
+/// Deletes every row keyed to the user, except in guilds under a legal hold.
+pub async fn delete(db: &PgPool, user_id: UserId) -> sqlx::Result<Deletion> {
+    time_query("privacy_delete", async {
+        let mut transaction = db.begin().await?;
+        let mut deletion = Deletion {
+            rows: 0,
+            held_guilds: Vec::new(),
+        };
+
+        for table in PERSONAL_DATA {
+            let held = match table.guild_column {
+                Some(guild) => format!("{guild} IN (SELECT guild_id FROM legal_holds)"),
+                None => "false".to_string(),
+            };
+
+            if let Some(guild) = table.guild_column {
+                let held_guilds: Vec<i64> = sqlx::query_scalar(&format!(
+                    "SELECT DISTINCT {guild} FROM {name} WHERE {matches} AND {held}",
+                    name = table.name,
+                    matches = table.matches_user(),
+                ))
+                .bind(i64::from(user_id))
+                .fetch_all(&mut *transaction)
+                .await?;
+                deletion.held_guilds.extend(
+                    held_guilds
+                        .into_iter()
+                        .filter_map(|id| u64::try_from(id).ok())
+                        .map(GuildId::new),
+                );
+            }
+
+            let result = sqlx::query(&format!(
+                "DELETE FROM {name} WHERE {matches} AND NOT ({held})",
+                name = table.name,
+                matches = table.matches_user(),
+            ))
+            .bind(i64::from(user_id))
+            .execute(&mut *transaction)
+            .await?;
+            deletion.rows += result.rows_affected();
+        }
+
+        transaction.commit().await?;
+        deletion.held_guilds.sort_unstable();
+        deletion.held_guilds.dedup();
+        Ok(deletion)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{balances, insert_user, GUILD, OTHER_USER, USER};
+
+    async fn place_legal_hold(db: &PgPool, guild_id: GuildId) {
+        sqlx::query("INSERT INTO legal_holds (guild_id, reason) VALUES ($1, 'test')")
+            .bind(i64::from(guild_id))
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn every_user_table_is_registered(db: PgPool) {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT table_name::text FROM information_schema.columns WHERE table_schema = 'public' AND column_name IN ('user_id', 'bucket')",
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+
+        for table in tables {
+            assert!(
+                PERSONAL_DATA.iter().any(|t| t.name == table),
+                "`{table}` has a user ID column but is missing from `PERSONAL_DATA`"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn exports_only_the_users_rows(db: PgPool) {
+        let other_guild = GuildId::new(2);
+        insert_user(&db, USER, GUILD, 5, 10).await;
+        insert_user(&db, USER, other_guild, 1, 0).await;
+        insert_user(&db, OTHER_USER, GUILD, 7, 0).await;
+
+        let export = export(&db, USER).await.unwrap();
+        let users = export["tables"]["users"].as_array().unwrap();
+        assert_eq!(users.len(), 2);
+        assert!(users.iter().all(|row| row["user_id"] == i64::from(USER)));
+        assert_eq!(
+            export["tables"]["users_without_guild"],
+            Value::Array(vec![])
+        );
+    }
+
+    #[sqlx::test]
+    async fn matches_archived_text_ids(db: PgPool) {
+        sqlx::query("INSERT INTO users_without_guild (user_id, guild_id) VALUES ($1, '')")
+            .bind(USER.to_string())
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let export = export(&db, USER).await.unwrap();
+        assert_eq!(
+            export["tables"]["users_without_guild"][0]["user_id"],
+            USER.to_string()
+        );
+        assert_eq!(delete(&db, USER).await.unwrap().rows, 1);
+    }
+
+    #[sqlx::test]
+    async fn covers_user_cooldowns(db: PgPool) {
+        for bucket in [
+            format!("user:{USER}"),
+            format!("user:{OTHER_USER}"),
+            format!("guild:{USER}"),
+        ] {
+            sqlx::query(
+                "INSERT INTO cooldowns (command, bucket, expires_at) VALUES ('work', $1, now())",
+            )
+            .bind(bucket)
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+
+        let export = export(&db, USER).await.unwrap();
+        assert_eq!(
+            export["tables"]["cooldowns"][0]["bucket"],
+            format!("user:{USER}")
+        );
+        assert_eq!(export["tables"]["cooldowns"].as_array().unwrap().len(), 1);
+        assert_eq!(delete(&db, USER).await.unwrap().rows, 1);
+    }
+
+    #[sqlx::test]
+    async fn deletes_everything_but_held_guilds(db: PgPool) {
+        let held_guild = GuildId::new(2);
+        insert_user(&db, USER, GUILD, 5, 10).await;
+        insert_user(&db, USER, held_guild, 1, 0).await;
+        insert_user(&db, OTHER_USER, GUILD, 7, 0).await;
+        place_legal_hold(&db, held_guild).await;
+
+        let deletion = delete(&db, USER).await.unwrap();
+        assert_eq!(
+            deletion,
+            Deletion {
+                rows: 1,
+                held_guilds: vec![held_guild]
+            }
+        );
+
+        assert_eq!(balances(&db, USER, held_guild).await, (1, 0));
+        assert_eq!(balances(&db, OTHER_USER, GUILD).await, (7, 0));
+    }
+}
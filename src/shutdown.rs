@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{watch, Notify};
+use tracing::warn;
+
+/// Coordinates a graceful shutdown: stops new commands, lets in-flight ones wrap up, and
+/// wakes anything waiting on user input so it can bail out early.
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: watch::Sender<bool>,
+    in_flight: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+/// Marks a command as in flight until dropped.
+pub struct InFlight {
+    in_flight: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+/// State kept for the duration of a single command invocation, stored in poise's
+/// invocation data.
+pub struct Invocation {
+    pub started_at: Instant,
+    _in_flight: InFlight,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: watch::Sender::new(false),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            idle: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    /// Resolves once a shutdown has started.
+    pub async fn triggered(&self) {
+        let mut rx = self.triggered.subscribe();
+        // The sender lives as long as `self`, so this can't fail.
+        let _ = rx.wait_for(|triggered| *triggered).await;
+    }
+
+    pub fn start_invocation(&self) -> Invocation {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        Invocation {
+            started_at: Instant::now(),
+            _in_flight: InFlight {
+                in_flight: self.in_flight.clone(),
+                idle: self.idle.clone(),
+            },
+        }
+    }
+
+    /// Waits for every in-flight command to finish, giving up after `timeout`.
+    pub async fn drain(&self, timeout: Duration) {
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+
+        if tokio::time::timeout(timeout, wait).await.is_err() {
+            warn!(
+                in_flight = self.in_flight.load(Ordering::SeqCst),
+                "Timed out waiting for commands to finish"
+            );
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+/// Resolves on SIGINT (Ctrl+C) or SIGTERM.
+pub async fn wait_for_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = interrupt => {},
+        () = terminate => {},
+    }
+}
@@ -0,0 +1,51 @@
+//! Fixtures shared by the command tests.
+
+use axum::Router;
+use poise::serenity_prelude::{CreateEmbed, GuildId, UserId};
+use reqwest::Url;
+use sqlx::PgPool;
+
+pub const GUILD: GuildId = GuildId::new(1);
+pub const USER: UserId = UserId::new(10);
+pub const OTHER_USER: UserId = UserId::new(20);
+
+/// Inserts a registered user with the given balances.
+pub async fn insert_user(db: &PgPool, user_id: UserId, guild_id: GuildId, wallet: i32, bank: i32) {
+    sqlx::query(
+        "INSERT INTO users (user_id, guild_id, wallet_balance, bank_balance) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(i64::from(user_id))
+    .bind(i64::from(guild_id))
+    .bind(wallet)
+    .bind(bank)
+    .execute(db)
+    .await
+    .unwrap();
+}
+
+/// Reads back a user's `(wallet, bank)` balances.
+pub async fn balances(db: &PgPool, user_id: UserId, guild_id: GuildId) -> (i32, i32) {
+    sqlx::query_as(
+        "SELECT wallet_balance, bank_balance FROM users WHERE user_id = $1 AND guild_id = $2",
+    )
+    .bind(i64::from(user_id))
+    .bind(i64::from(guild_id))
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+/// Serves `app` on a free local port in the background, returning its base URL.
+pub async fn serve(app: Router) -> Url {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url.parse().unwrap()
+}
+
+pub fn embed_title(embed: &CreateEmbed) -> String {
+    serde_json::to_value(embed).unwrap()["title"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
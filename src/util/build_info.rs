@@ -0,0 +1,6 @@
+//! Build metadata emitted by `vergen` in `build.rs`.
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
+pub const RUSTC_SEMVER: &str = env!("VERGEN_RUSTC_SEMVER");
+pub const GIT_SHA: &str = env!("VERGEN_GIT_SHA");
@@ -1,32 +1,91 @@
-use poise::serenity_prelude::{GuildId, UserId};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+use crate::config::DatabaseConfig;
+
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+const POOL_STATS_INTERVAL: Duration = Duration::from_secs(5 * 60);
 
-pub struct UserBalances {
-    pub bank_balance: i32,
-    pub wallet_balance: i32,
+/// Connects to Postgres, retrying with exponential backoff if the database isn't reachable yet.
+pub async fn connect(config: &DatabaseConfig) -> sqlx::Result<PgPool> {
+    let connect_options = PgConnectOptions::from_str(config.url.as_str())?
+        .statement_cache_capacity(config.statement_cache_capacity);
+    let pool_options = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout);
+
+    let mut backoff = Duration::from_secs(1);
+    let mut attempt = 1;
+    loop {
+        match pool_options
+            .clone()
+            .connect_with(connect_options.clone())
+            .await
+        {
+            Ok(pool) => return Ok(pool),
+            Err(err) if attempt < config.connect_attempts => {
+                warn!(
+                    attempt,
+                    ?backoff,
+                    error = %err,
+                    "Failed to connect to the database, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
 }
 
-impl UserBalances {
-    pub async fn from_user_and_guild_ids(
-        user_id: UserId,
-        guild_id: GuildId,
-        db: &PgPool,
-    ) -> sqlx::Result<Self> {
-        let record = sqlx::query!(
-            "
-    SELECT bank_balance, wallet_balance
-    FROM users
-    WHERE user_id = $1 AND guild_id = $2
-        ",
-            user_id.to_string(),
-            guild_id.to_string()
-        )
-        .fetch_one(db)
-        .await?;
+/// A snapshot of the connection pool's health.
+pub struct PoolStats {
+    pub max: u32,
+    pub idle: usize,
+    pub active: u32,
+    /// How long it took to check out a connection when the snapshot was taken.
+    pub acquire_wait: Duration,
+}
+
+impl PoolStats {
+    pub async fn sample(pool: &PgPool) -> sqlx::Result<Self> {
+        let size = pool.size();
+        let idle = pool.num_idle();
+
+        let start = Instant::now();
+        drop(pool.acquire().await?);
+        let acquire_wait = start.elapsed();
 
         Ok(Self {
-            bank_balance: record.bank_balance,
-            wallet_balance: record.wallet_balance,
+            max: pool.options().get_max_connections(),
+            idle,
+            active: size.saturating_sub(u32::try_from(idle).unwrap_or(u32::MAX)),
+            acquire_wait,
         })
     }
 }
+
+/// Periodically logs [`PoolStats`], so operators can spot an exhausted or unreachable pool.
+pub async fn log_pool_stats(pool: PgPool) {
+    let mut interval = tokio::time::interval(POOL_STATS_INTERVAL);
+    loop {
+        interval.tick().await;
+        match PoolStats::sample(&pool).await {
+            Ok(stats) => info!(
+                active = stats.active,
+                idle = stats.idle,
+                max = stats.max,
+                acquire_wait = ?stats.acquire_wait,
+                "Database pool stats"
+            ),
+            Err(err) => error!(error = %err, "Failed to check out a database connection"),
+        }
+    }
+}
@@ -1,3 +1,6 @@
+pub mod build_info;
 pub mod db;
 pub mod image_urls;
+pub mod public_http;
+pub mod text;
 pub mod timestamp;
@@ -0,0 +1,153 @@
+//! An HTTP client for URLs that users give Avion, which won't connect to private networks,
+//! so they can't be used to reach services only Avion's host can see.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::{Action, Attempt, Policy};
+use reqwest::Url;
+
+/// How many redirects to follow, as many as reqwest follows by default.
+const MAX_REDIRECTS: usize = 10;
+
+/// Builds a client that only connects to public addresses, including when redirected.
+///
+/// Hosts are checked as they're resolved, so they can't pass a check and then resolve to a
+/// private address when connected to.
+pub fn client(timeout: Duration) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(Policy::custom(redirect))
+        .build()
+}
+
+/// Whether `url`'s host is, or resolves to, a private address. Hosts that don't resolve
+/// aren't, and fail once they're requested instead.
+pub async fn is_private(url: &Url) -> bool {
+    if let Some(ip) = ip_host(url) {
+        return !is_public(ip);
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let port = url.port_or_known_default().unwrap_or_default();
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(mut addresses) => addresses.any(|address| !is_public(address.ip())),
+        Err(_) => false,
+    }
+}
+
+/// The IP address `url`'s host is written as, if it isn't a name.
+fn ip_host(url: &Url) -> Option<IpAddr> {
+    let host = url.host_str()?;
+    // IPv6 addresses are bracketed in URLs
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// Resolves hosts to their public addresses only.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no public addresses", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Follows redirects, except to private IP addresses. Redirects to hosts by name go through
+/// [`PublicResolver`] instead.
+fn redirect(attempt: Attempt<'_>) -> Action {
+    if ip_host(attempt.url()).is_some_and(|ip| !is_public(ip)) {
+        attempt.error("redirected to a private address")
+    } else if attempt.previous().len() >= MAX_REDIRECTS {
+        attempt.error("too many redirects")
+    } else {
+        attempt.follow()
+    }
+}
+
+/// Whether `ip` can be reached over the internet, rather than only from a private network
+/// or the host itself.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => ip
+            .to_ipv4_mapped()
+            .map_or_else(|| is_public_v6(ip), is_public_v4),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network"
+        || a == 0
+        // Shared by carrier-grade NATs
+        || (a == 100 && (64..128).contains(&b))
+        // Protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let [first, second, ..] = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local
+        || first & 0xfe00 == 0xfc00
+        // Link-local
+        || first & 0xffc0 == 0xfe80
+        // Documentation
+        || (first == 0x2001 && second == 0xdb8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_public_addresses_apart() {
+        for public in ["1.1.1.1", "93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public(public.parse().unwrap()), "{public}");
+        }
+        for private in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(private.parse().unwrap()), "{private}");
+        }
+    }
+}
@@ -0,0 +1,21 @@
+/// Shortens `text` to at most `max` characters, ending it with an ellipsis if it was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_by_character() {
+        assert_eq!(truncate("Standards", 9), "Standards");
+        assert_eq!(truncate("Standards", 5), "Stan…");
+        assert_eq!(truncate("Größe", 3), "Gr…");
+    }
+}
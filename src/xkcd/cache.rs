@@ -0,0 +1,90 @@
+//! Comic metadata cached in memory, and optionally in Postgres so it survives restarts.
+//!
+//! Comics never change once published, so cached entries are kept until they're evicted.
+//! Fetched comics are always stored in Postgres, as that's what `/xkcd search` searches.
+
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+use sqlx::PgPool;
+use tracing::warn;
+
+use super::Xkcd;
+use crate::metrics::time_query;
+
+pub struct Cache {
+    memory: Mutex<LruCache<usize, Xkcd>>,
+    db: PgPool,
+    /// Whether to serve comics from the database when they aren't in memory.
+    persist: bool,
+}
+
+impl Cache {
+    pub fn new(capacity: NonZeroUsize, db: PgPool, persist: bool) -> Self {
+        Self {
+            memory: Mutex::new(LruCache::new(capacity)),
+            db,
+            persist,
+        }
+    }
+
+    pub async fn get(&self, num: usize) -> Option<Xkcd> {
+        if let Some(comic) = self.memory.lock().unwrap().get(&num) {
+            return Some(comic.clone());
+        }
+
+        let comic = self.load(num).await?;
+        self.memory.lock().unwrap().put(num, comic.clone());
+        Some(comic)
+    }
+
+    pub async fn insert(&self, comic: &Xkcd) {
+        self.memory.lock().unwrap().put(comic.num, comic.clone());
+        if let Err(err) = self.store(comic).await {
+            warn!(error = %err, num = comic.num, "Failed to store comic in the database");
+        }
+    }
+
+    /// Loads a comic from the database. Failures are logged and treated as a miss, so the
+    /// comic is fetched from upstream instead.
+    async fn load(&self, num: usize) -> Option<Xkcd> {
+        if !self.persist {
+            return None;
+        }
+        let data = time_query(
+            "get_xkcd_comic",
+            sqlx::query_scalar!(
+                "SELECT data FROM xkcd_comics WHERE num = $1",
+                i32::try_from(num).ok()?
+            )
+            .fetch_optional(&self.db),
+        )
+        .await
+        .inspect_err(|err| warn!(error = %err, num, "Failed to load comic from the database"))
+        .ok()??;
+
+        serde_json::from_value(data)
+            .inspect_err(|err| warn!(error = %err, num, "Stored comic is invalid"))
+            .ok()
+    }
+
+    /// Stores a comic in the database only, for comics that aren't likely to be viewed soon.
+    pub async fn store(&self, comic: &Xkcd) -> Result<(), crate::Error> {
+        time_query(
+            "store_xkcd_comic",
+            sqlx::query!(
+                "
+                INSERT INTO xkcd_comics (num, data) VALUES ($1, $2)
+                ON CONFLICT (num) DO UPDATE SET data = EXCLUDED.data, fetched_at = now()
+                ",
+                i32::try_from(comic.num)?,
+                serde_json::to_value(comic)?
+            )
+            .execute(&self.db),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,521 @@
+//! Fetching xkcd comics, served from a cache where possible.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use poise::serenity_prelude::{Colour, CreateButton, CreateEmbed, Timestamp};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::comics::{ComicError, ComicProvider, Source};
+use crate::config::XkcdConfig;
+use crate::i18n::{tr, Locale};
+use crate::metrics::METRICS;
+use crate::util::text::truncate;
+use crate::util::timestamp::{Format, TimestampExt};
+use crate::Error;
+
+mod cache;
+use cache::Cache;
+pub mod search;
+
+/// Discord's limit on embed field values.
+const MAX_FIELD_LENGTH: usize = 1024;
+/// There's no comic 404, as a joke.
+pub const MISSING_COMIC: usize = 404;
+/// How long to wait before the first retry, doubling each time.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Xkcd {
+    pub safe_title: String,
+    pub alt: String,
+    #[serde(rename = "img")]
+    pub image_url: String,
+    pub num: usize,
+    /// The publication date, as xkcd.com gives it: unpadded numbers in strings.
+    pub year: String,
+    pub month: String,
+    pub day: String,
+    /// Where clicking the comic leads, which is empty for most comics.
+    #[serde(default)]
+    pub link: String,
+    /// Only older comics have transcripts.
+    #[serde(default)]
+    pub transcript: String,
+}
+
+impl Xkcd {
+    /// The comic's page on xkcd.com.
+    pub fn page_url(&self) -> String {
+        format!("https://xkcd.com/{}/", self.num)
+    }
+
+    /// The comic's page on explainxkcd.com.
+    pub fn explain_url(&self) -> String {
+        format!("https://www.explainxkcd.com/wiki/index.php/{}", self.num)
+    }
+
+    /// When the comic was published, if xkcd.com gave a valid date.
+    pub fn published(&self) -> Option<Timestamp> {
+        let year: u16 = self.year.parse().ok()?;
+        let month: u8 = self.month.parse().ok()?;
+        let day: u8 = self.day.parse().ok()?;
+        Timestamp::parse(&format!("{year:04}-{month:02}-{day:02}T00:00:00Z")).ok()
+    }
+
+    pub fn into_embed(self, locale: Locale) -> CreateEmbed {
+        let mut embed = CreateEmbed::new()
+            .title(format!("#{}: {}", self.num, self.safe_title))
+            .url(self.page_url())
+            .colour(Colour::BLUE);
+        if let Some(published) = self.published() {
+            embed = embed.field(
+                tr!(locale, "xkcd-published"),
+                published.to_discord_timestamp(Format::LongDate),
+                true,
+            );
+        }
+        if !self.link.is_empty() {
+            embed = embed.field(tr!(locale, "xkcd-link"), self.link, true);
+        }
+        if !self.transcript.is_empty() {
+            embed = embed.field(
+                tr!(locale, "xkcd-transcript"),
+                truncate(&self.transcript, MAX_FIELD_LENGTH),
+                false,
+            );
+        }
+
+        // Descriptions fit 4096 characters, so long alt text isn't cut off like in a footer
+        embed.description(self.alt).image(self.image_url)
+    }
+}
+
+/// The latest comic's number, and when it was checked.
+struct Latest {
+    num: usize,
+    checked_at: Instant,
+}
+
+pub struct XkcdClient {
+    client: reqwest::Client,
+    base_url: Url,
+    cache: Cache,
+    latest: Mutex<Option<Latest>>,
+    latest_ttl: Duration,
+    retries: u32,
+}
+
+impl XkcdClient {
+    pub fn new(client: reqwest::Client, db: PgPool, config: &XkcdConfig) -> Self {
+        // Without a trailing slash, `Url::join` would replace the last path segment
+        let mut base_url = config.base_url.clone();
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Self {
+            client,
+            base_url,
+            cache: Cache::new(config.cache_size, db, config.persist_cache),
+            latest: Mutex::new(None),
+            latest_ttl: config.latest_ttl,
+            retries: config.retries,
+        }
+    }
+
+    /// Gets the latest comic, checking for a new one once the last check is older than the
+    /// configured TTL. If xkcd.com can't be reached, the last known latest comic is used.
+    pub async fn latest(&self) -> Result<Xkcd, ComicError> {
+        if let Some(num) = self.latest_num_if_fresh() {
+            if let Some(comic) = self.cache.get(num).await {
+                return Ok(comic);
+            }
+        }
+
+        match self.fetch(None).await {
+            Ok(comic) => {
+                self.cache.insert(&comic).await;
+                *self.latest.lock().unwrap() = Some(Latest {
+                    num: comic.num,
+                    checked_at: Instant::now(),
+                });
+                Ok(comic)
+            }
+            Err(err) => {
+                let stale = self
+                    .latest
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map(|latest| latest.num);
+                if let Some(num) = stale {
+                    if let Some(comic) = self.cache.get(num).await {
+                        warn!(error = %err, "Failed to check for a new comic, using the last one");
+                        return Ok(comic);
+                    }
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Gets a comic by number, without asking xkcd.com about numbers that can't exist.
+    pub async fn get(&self, num: usize) -> Result<Xkcd, ComicError> {
+        if let Some(comic) = self.cache.get(num).await {
+            return Ok(comic);
+        }
+        if num == 0 || num == MISSING_COMIC || num > self.latest_num().await? {
+            return Err(ComicError::NotFound(num));
+        }
+
+        let comic = self.fetch(Some(num)).await?;
+        self.cache.insert(&comic).await;
+        Ok(comic)
+    }
+
+    /// Fetches a comic into the database only, so the search index can be filled without
+    /// evicting comics people are viewing.
+    pub async fn index(&self, num: usize) -> Result<(), Error> {
+        let comic = self.fetch(Some(num)).await?;
+        self.cache.store(&comic).await
+    }
+
+    pub async fn random(&self) -> Result<Xkcd, ComicError> {
+        let latest = self.latest_num().await?;
+        let num = loop {
+            let num = fastrand::usize(1..=latest);
+            if num != MISSING_COMIC {
+                break num;
+            }
+        };
+        self.get(num).await
+    }
+
+    async fn latest_num(&self) -> Result<usize, ComicError> {
+        match self.latest_num_if_fresh() {
+            Some(num) => Ok(num),
+            None => Ok(self.latest().await?.num),
+        }
+    }
+
+    fn latest_num_if_fresh(&self) -> Option<usize> {
+        self.latest
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|latest| latest.checked_at.elapsed() < self.latest_ttl)
+            .map(|latest| latest.num)
+    }
+
+    /// Fetches a comic from xkcd.com, or the latest one if `num` is `None`, retrying
+    /// transient failures.
+    async fn fetch(&self, num: Option<usize>) -> Result<Xkcd, ComicError> {
+        let path = match num {
+            Some(num) => format!("{num}/info.0.json"),
+            None => "info.0.json".to_string(),
+        };
+        let url = self
+            .base_url
+            .join(&path)
+            .expect("the base URL is absolute and ends in a slash");
+
+        let mut attempt = 0;
+        loop {
+            let start = Instant::now();
+            let result = self.request(url.clone(), num).await;
+            METRICS.observe_xkcd_request(
+                result.as_ref().map_or_else(ComicError::outcome, |_| "ok"),
+                start,
+            );
+
+            match result {
+                Err(err) if err.is_transient() && attempt < self.retries => {
+                    warn!(error = %err, attempt, "xkcd.com request failed, retrying");
+                    tokio::time::sleep(RETRY_DELAY * 2u32.pow(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn request(&self, url: Url, num: Option<usize>) -> Result<Xkcd, ComicError> {
+        let response = self.client.get(url).send().await?;
+        if let (Some(num), StatusCode::NOT_FOUND) = (num, response.status()) {
+            return Err(ComicError::NotFound(num));
+        }
+        Ok(response.error_for_status()?.json::<Xkcd>().await?)
+    }
+}
+
+impl ComicProvider for XkcdClient {
+    type Comic = Xkcd;
+
+    fn source(&self) -> Source {
+        Source::Xkcd
+    }
+
+    fn name(&self) -> &'static str {
+        "xkcd.com"
+    }
+
+    fn latest(&self) -> impl Future<Output = Result<Xkcd, ComicError>> + Send {
+        Self::latest(self)
+    }
+
+    fn by_id(&self, id: usize) -> impl Future<Output = Result<Xkcd, ComicError>> + Send {
+        self.get(id)
+    }
+
+    fn random(&self) -> impl Future<Output = Result<Xkcd, ComicError>> + Send {
+        Self::random(self)
+    }
+
+    fn id(&self, comic: &Xkcd) -> usize {
+        comic.num
+    }
+
+    fn into_embed(&self, comic: Xkcd, locale: Locale) -> CreateEmbed {
+        comic.into_embed(locale)
+    }
+
+    fn is_missing(&self, id: usize) -> bool {
+        id == MISSING_COMIC
+    }
+
+    fn links(&self, comic: &Xkcd, locale: Locale) -> Vec<CreateButton> {
+        vec![CreateButton::new_link(comic.explain_url()).label(tr!(locale, "xkcd-button-explain"))]
+    }
+
+    fn error_embed(&self, err: ComicError, locale: Locale) -> CreateEmbed {
+        if let ComicError::NotFound(MISSING_COMIC) = err {
+            return CreateEmbed::new()
+                .title(tr!(locale, "comic-not-found-title"))
+                .description(tr!(locale, "xkcd-not-found-404"))
+                .colour(Colour::RED);
+        }
+        err.into_embed(self.name(), locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use axum::extract::{Path, State};
+    use axum::routing::get;
+    use axum::{Json, Router};
+
+    use super::*;
+    use crate::test_utils::serve;
+
+    const LATEST: usize = 3;
+
+    fn comic(num: usize) -> Xkcd {
+        Xkcd {
+            safe_title: format!("Comic {num}"),
+            alt: String::new(),
+            image_url: format!("https://imgs.xkcd.com/comics/{num}.png"),
+            num,
+            year: "2009".to_string(),
+            month: "2".to_string(),
+            day: "13".to_string(),
+            link: String::new(),
+            transcript: String::new(),
+        }
+    }
+
+    /// Serves comics 1 to `LATEST` like xkcd.com, counting the requests it gets. Comics
+    /// after `LATEST` are missing.
+    async fn stub_server() -> (Url, Arc<AtomicUsize>) {
+        async fn latest(State(hits): State<Arc<AtomicUsize>>) -> Json<Xkcd> {
+            hits.fetch_add(1, Ordering::SeqCst);
+            Json(comic(LATEST))
+        }
+        async fn by_num(
+            State(hits): State<Arc<AtomicUsize>>,
+            Path(num): Path<usize>,
+        ) -> Result<Json<Xkcd>, StatusCode> {
+            hits.fetch_add(1, Ordering::SeqCst);
+            if num > LATEST {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            Ok(Json(comic(num)))
+        }
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route("/info.0.json", get(latest))
+            .route("/:num/info.0.json", get(by_num))
+            .with_state(hits.clone());
+
+        (serve(app).await, hits)
+    }
+
+    /// Serves the latest comic, failing the first `failures` requests with `status`, or by
+    /// not responding in time if `status` is `None`.
+    async fn flaky_server(failures: usize, status: Option<StatusCode>) -> Url {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/info.0.json",
+                get(move |State(hits): State<Arc<AtomicUsize>>| async move {
+                    if hits.fetch_add(1, Ordering::SeqCst) >= failures {
+                        return Ok(Json(comic(LATEST)));
+                    }
+                    if status.is_none() {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                    Err(status.unwrap_or(StatusCode::OK))
+                }),
+            )
+            .with_state(hits);
+
+        serve(app).await
+    }
+
+    fn config(base_url: Url, persist_cache: bool) -> XkcdConfig {
+        XkcdConfig {
+            base_url,
+            cache_size: NonZeroUsize::new(10).unwrap(),
+            latest_ttl: Duration::from_secs(60),
+            persist_cache,
+            timeout: Duration::from_millis(200),
+            retries: 1,
+        }
+    }
+
+    fn client(db: PgPool, base_url: Url, persist_cache: bool) -> XkcdClient {
+        let config = config(base_url, persist_cache);
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap();
+        XkcdClient::new(http, db, &config)
+    }
+
+    #[test]
+    fn embed_links_the_comic_page() {
+        let comic = Xkcd {
+            alt: "a".repeat(3000),
+            transcript: "t".repeat(2000),
+            ..comic(927)
+        };
+        let embed = serde_json::to_value(comic.into_embed(Locale::ENGLISH)).unwrap();
+
+        assert_eq!(embed["title"], "#927: Comic 927");
+        assert_eq!(embed["url"], "https://xkcd.com/927/");
+        assert_eq!(embed["description"].as_str().unwrap().len(), 3000);
+        assert_eq!(embed["fields"][0]["value"], "<t:1234483200:D>");
+        assert_eq!(
+            embed["fields"][1]["value"]
+                .as_str()
+                .unwrap()
+                .chars()
+                .count(),
+            MAX_FIELD_LENGTH
+        );
+    }
+
+    #[sqlx::test]
+    async fn repeated_lookups_are_cached(db: PgPool) {
+        let (url, hits) = stub_server().await;
+        let xkcd = client(db, url, false);
+
+        assert_eq!(xkcd.get(2).await.unwrap(), comic(2));
+        assert_eq!(xkcd.get(2).await.unwrap(), comic(2));
+        assert_eq!(xkcd.latest().await.unwrap(), comic(LATEST));
+        assert_eq!(xkcd.latest().await.unwrap(), comic(LATEST));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[sqlx::test]
+    async fn rejects_comics_that_cannot_exist(db: PgPool) {
+        let (url, hits) = stub_server().await;
+        let xkcd = client(db, url, false);
+        xkcd.latest().await.unwrap();
+
+        for num in [0, MISSING_COMIC, LATEST + 1] {
+            assert!(matches!(xkcd.get(num).await, Err(ComicError::NotFound(n)) if n == num));
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // Skipping the checks, xkcd.com's 404 is still told apart from other failures
+        let err = xkcd.index(LATEST + 1).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ComicError>(),
+            Some(ComicError::NotFound(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn retries_transient_failures(db: PgPool) {
+        let url = flaky_server(1, Some(StatusCode::BAD_GATEWAY)).await;
+        assert_eq!(
+            client(db.clone(), url, false).latest().await.unwrap(),
+            comic(LATEST)
+        );
+
+        let url = flaky_server(2, Some(StatusCode::BAD_GATEWAY)).await;
+        assert!(matches!(
+            client(db.clone(), url, false).latest().await,
+            Err(ComicError::Upstream(_))
+        ));
+
+        let url = flaky_server(2, None).await;
+        assert!(matches!(
+            client(db, url, false).latest().await,
+            Err(ComicError::Timeout)
+        ));
+    }
+
+    #[sqlx::test]
+    async fn random_is_served_locally(db: PgPool) {
+        let (url, hits) = stub_server().await;
+        let xkcd = client(db, url, false);
+        for num in 1..=LATEST {
+            xkcd.get(num).await.unwrap();
+        }
+        xkcd.latest().await.unwrap();
+
+        let before = hits.load(Ordering::SeqCst);
+        for _ in 0..10 {
+            assert!((1..=LATEST).contains(&xkcd.random().await.unwrap().num));
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), before);
+    }
+
+    #[sqlx::test]
+    async fn persisted_comics_survive_restarts(db: PgPool) {
+        let (url, _) = stub_server().await;
+        client(db.clone(), url, true).get(2).await.unwrap();
+
+        // Nothing listens on port 9 (discard), so any upstream request would fail
+        let offline = client(db, "http://127.0.0.1:9".parse().unwrap(), true);
+        assert_eq!(offline.get(2).await.unwrap(), comic(2));
+        assert!(offline.get(1).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn sync_indexes_missing_comics(db: PgPool) {
+        let (url, _) = stub_server().await;
+        let xkcd = client(db.clone(), url, false);
+        xkcd.get(2).await.unwrap();
+
+        // Fetching the latest comic indexes it too, leaving only the first
+        assert_eq!(search::sync(&xkcd, &db).await.unwrap(), 1);
+        assert_eq!(search::sync(&xkcd, &db).await.unwrap(), 0);
+        let indexed: i64 = sqlx::query_scalar("SELECT count(*) FROM xkcd_comics")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(indexed, 3);
+    }
+}
@@ -0,0 +1,200 @@
+//! Full-text search over every comic's title, alt text and transcript.
+//!
+//! The index is the `xkcd_comics` table, which a background sync fills with every comic.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+use super::{XkcdClient, MISSING_COMIC};
+use crate::metrics::time_query;
+use crate::Error;
+
+/// How often to check for comics missing from the index.
+const SYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How long to wait between requests while syncing, to go easy on xkcd.com.
+const SYNC_DELAY: Duration = Duration::from_millis(200);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    pub num: usize,
+    pub title: String,
+}
+
+/// Finds comics matching `query`, best matches first. Matches in titles rank above matches
+/// in alt text, which rank above matches in transcripts.
+pub async fn search(db: &PgPool, query: &str, limit: i64) -> sqlx::Result<Vec<SearchResult>> {
+    let records = time_query(
+        "search_xkcd_comics",
+        sqlx::query!(
+            r#"
+            SELECT num, data->>'safe_title' AS "title!"
+            FROM xkcd_comics, websearch_to_tsquery('english', $1) query
+            WHERE search @@ query
+            ORDER BY ts_rank(search, query) DESC, num DESC
+            LIMIT $2
+            "#,
+            query,
+            limit
+        )
+        .fetch_all(db),
+    )
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| {
+            Some(SearchResult {
+                num: usize::try_from(record.num).ok()?,
+                title: record.title,
+            })
+        })
+        .collect())
+}
+
+/// Finds comics whose title contains `partial`, newest first.
+pub async fn by_title(db: &PgPool, partial: &str, limit: i64) -> sqlx::Result<Vec<SearchResult>> {
+    let records = time_query(
+        "xkcd_comics_by_title",
+        sqlx::query!(
+            r#"
+            SELECT num, data->>'safe_title' AS "title!"
+            FROM xkcd_comics
+            WHERE strpos(lower(data->>'safe_title'), lower($1)) > 0
+            ORDER BY num DESC
+            LIMIT $2
+            "#,
+            partial,
+            limit
+        )
+        .fetch_all(db),
+    )
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| {
+            Some(SearchResult {
+                num: usize::try_from(record.num).ok()?,
+                title: record.title,
+            })
+        })
+        .collect())
+}
+
+/// Fetches every comic that isn't in the index yet, returning how many were added.
+pub async fn sync(xkcd: &XkcdClient, db: &PgPool) -> Result<usize, Error> {
+    let latest = i32::try_from(xkcd.latest().await?.num)?;
+    let missing = time_query(
+        "missing_xkcd_comics",
+        sqlx::query_scalar!(
+            r#"
+            SELECT num AS "num!"
+            FROM generate_series(1, $1) num
+            WHERE num <> $2 AND num NOT IN (SELECT num FROM xkcd_comics)
+            "#,
+            latest,
+            i32::try_from(MISSING_COMIC)?
+        )
+        .fetch_all(db),
+    )
+    .await?;
+
+    let mut added = 0;
+    for num in missing {
+        match xkcd.index(usize::try_from(num)?).await {
+            Ok(()) => added += 1,
+            // Try again next time
+            Err(err) => warn!(error = %err, num, "Failed to index comic"),
+        }
+        tokio::time::sleep(SYNC_DELAY).await;
+    }
+
+    Ok(added)
+}
+
+/// Periodically adds new comics to the index.
+pub async fn run_sync(xkcd: Arc<XkcdClient>, db: PgPool) {
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+    loop {
+        interval.tick().await;
+        match sync(&xkcd, &db).await {
+            Ok(0) => {}
+            Ok(comics) => info!(comics, "Added comics to the search index"),
+            Err(err) => error!(error = %err, "Failed to sync the comic search index"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert(db: &PgPool, num: i32, title: &str, alt: &str, transcript: &str) {
+        let data = serde_json::json!({
+            "num": num,
+            "safe_title": title,
+            "alt": alt,
+            "img": "",
+            "transcript": transcript,
+        });
+        sqlx::query("INSERT INTO xkcd_comics (num, data) VALUES ($1, $2)")
+            .bind(num)
+            .bind(data)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    fn result(num: usize, title: &str) -> SearchResult {
+        SearchResult {
+            num,
+            title: title.to_string(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn ranks_titles_first(db: PgPool) {
+        insert(&db, 1, "Barrel", "", "Someone mentions standards.").await;
+        insert(
+            &db,
+            927,
+            "Standards",
+            "Fortunately, the charging one has been solved.",
+            "",
+        )
+        .await;
+        insert(&db, 3, "Other", "Competing standards everywhere", "").await;
+        insert(&db, 4, "Unrelated", "", "").await;
+
+        assert_eq!(
+            search(&db, "standards", 10).await.unwrap(),
+            vec![
+                result(927, "Standards"),
+                result(3, "Other"),
+                result(1, "Barrel")
+            ]
+        );
+        assert_eq!(search(&db, "nothing like this", 10).await.unwrap(), vec![]);
+    }
+
+    #[sqlx::test]
+    async fn finds_by_partial_title(db: PgPool) {
+        insert(&db, 927, "Standards", "", "").await;
+        insert(&db, 1000, "1000 Comics", "", "").await;
+
+        assert_eq!(
+            by_title(&db, "stand", 25).await.unwrap(),
+            vec![result(927, "Standards")]
+        );
+        assert_eq!(
+            by_title(&db, "", 1).await.unwrap(),
+            vec![result(1000, "1000 Comics")]
+        );
+        // Wildcards are matched literally
+        assert_eq!(by_title(&db, "%", 25).await.unwrap(), vec![]);
+        assert_eq!(by_title(&db, "_", 25).await.unwrap(), vec![]);
+    }
+}